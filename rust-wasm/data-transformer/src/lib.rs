@@ -1,11 +1,23 @@
 use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
 use ahash::AHashMap;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use flate2::Compression;
 use flate2::write::{GzEncoder, GzDecoder};
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
 use std::io::Write;
 
+// Gzip ID bytes plus the deflate compression method
+const GZIP_MAGIC: [u8; 3] = [0x1f, 0x8b, 0x08];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+// Upper bound on LZ4's compression ratio, used to sanity-check a size prefix
+const LZ4_MAX_RATIO: usize = 255;
+const DEFAULT_MAX_CACHE_ENTRIES: usize = 100;
+
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
@@ -14,17 +26,103 @@ pub fn init_panic_hook() {
     console_error_panic_hook::set_once();
 }
 
+/// Category of a failure, so JS callers can branch on `code` instead of
+/// matching message text
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// JSON, CSV or config input couldn't be parsed
+    ParseError,
+    /// Input parsed but isn't shaped as required
+    ValidationError,
+    /// A strict-mode batch hit a failing field transform
+    TransformError,
+    /// Compressing or decompressing failed, or the format is unknown
+    CompressionError,
+    /// Bytes aren't valid UTF-8 or base64
+    DecodeError,
+    /// Output couldn't be written as JSON or CSV
+    SerializationError,
+}
+
+/// Error surfaced to JS as a `{code, message, field}` object
+#[derive(Serialize, Debug, Clone)]
+pub struct TransformerError {
+    pub code: ErrorCode,
+    pub message: String,
+    /// The argument at fault, when a single one is
+    pub field: Option<String>,
+}
+
+impl TransformerError {
+    fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        TransformerError { code, message: message.into(), field: None }
+    }
+
+    fn parse(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::ParseError, message)
+    }
+
+    fn validation(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::ValidationError, message)
+    }
+
+    fn transform(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::TransformError, message)
+    }
+
+    fn compression(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::CompressionError, message)
+    }
+
+    fn decode(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::DecodeError, message)
+    }
+
+    fn serialization(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::SerializationError, message)
+    }
+
+    fn with_field(mut self, field: &str) -> Self {
+        self.field = Some(field.to_string());
+        self
+    }
+}
+
+impl From<TransformerError> for JsValue {
+    fn from(error: TransformerError) -> Self {
+        error.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .unwrap_or_else(|_| JsValue::from_str(&error.message))
+    }
+}
+
+/// Per-item operations run in a fixed order: remove, keep, rename, transform,
+/// conditional transforms. Renames are applied together, so chained mappings
+/// (a -> b, b -> c) don't depend on map order. Transforms resolve against the
+/// renamed object, and a transform keyed on a pre-rename name follows the
+/// field to its new name.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TransformConfig {
     pub fields_to_keep: Option<Vec<String>>,
     pub fields_to_remove: Option<Vec<String>>,
     pub field_mappings: Option<AHashMap<String, String>>,
-    pub field_transforms: Option<AHashMap<String, TransformType>>,
+    pub field_transforms: Option<AHashMap<String, FieldTransform>>,
     pub filters: Option<Vec<FilterConfig>>,
     pub aggregations: Option<Vec<AggregationConfig>>,
+    /// Evaluated in order, each against the item as left by the previous one
+    pub conditional_transforms: Option<Vec<ConditionalTransform>>,
+    /// JSON Schema each input item must satisfy; items that don't are
+    /// reported in the errors list and not transformed
+    pub schema: Option<Value>,
+    /// Abort the batch on the first schema violation or transform error
+    /// instead of collecting it
+    pub strict: Option<bool>,
 }
 
+// Derived through `remote = "Self"` so the Deserialize impl below can map
+// the pre-HashAlgo bare "Hash" onto the legacy algorithm
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(remote = "Self")]
 pub enum TransformType {
     Lowercase,
     Uppercase,
@@ -32,10 +130,84 @@ pub enum TransformType {
     Round(u32),
     Multiply(f64),
     Add(f64),
+    /// Reformat an RFC 3339, "YYYY-MM-DDTHH:MM:SS" or "YYYY-MM-DD" string
+    /// with a strftime-style format; unparseable dates fail
     DateFormat(String),
     Replace(String, String),
-    Hash,
+    /// Bare "Hash", as configs written before HashAlgo use, means Legacy
+    Hash(HashAlgo),
     Truncate(usize),
+    /// Parse strings/bools into numbers, passing other values through
+    ToNumber,
+    /// Stringify numbers and bools
+    ToString,
+    /// Map "true"/"false"/1/0 to booleans, passing other values through
+    ToBool,
+    /// Split a string on the delimiter into an array of trimmed, non-empty
+    /// parts
+    Split(String),
+    /// Join array elements with the delimiter, skipping nulls
+    Join(String),
+    /// Replace null, missing or blank-string values with the given default
+    DefaultIfEmpty(Value),
+}
+
+impl Serialize for TransformType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        TransformType::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TransformType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        if value == "Hash" {
+            return Ok(TransformType::Hash(HashAlgo::Legacy));
+        }
+        TransformType::deserialize(value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Hash algorithm for TransformType::Hash. Output is lowercase hex.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum HashAlgo {
+    Sha256,
+    Blake3,
+    /// The original 32-bit multiply-accumulate hash; not collision resistant
+    Legacy,
+}
+
+/// One transform, or a chain applied left-to-right with each stage's output
+/// feeding the next
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum FieldTransform {
+    Single(TransformType),
+    Chain(Vec<TransformType>),
+}
+
+impl FieldTransform {
+    fn stages(&self) -> &[TransformType] {
+        match self {
+            FieldTransform::Single(transform) => std::slice::from_ref(transform),
+            FieldTransform::Chain(transforms) => transforms,
+        }
+    }
+
+    /// Whether the transform should also run on fields absent from an item
+    fn fills_missing(&self) -> bool {
+        self.stages().iter().any(|t| matches!(t, TransformType::DefaultIfEmpty(_)))
+    }
+}
+
+/// Transform `field` only when the item matches `when`, applying
+/// `otherwise` (if given) when it doesn't
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConditionalTransform {
+    pub when: FilterConfig,
+    pub field: String,
+    pub transform: FieldTransform,
+    pub otherwise: Option<FieldTransform>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -43,6 +215,9 @@ pub struct FilterConfig {
     pub field: String,
     pub operator: FilterOperator,
     pub value: Value,
+    /// Lowercase both sides of string comparisons (Equals, NotEquals,
+    /// Contains, StartsWith, EndsWith). Defaults to case-sensitive.
+    pub case_insensitive: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -64,6 +239,8 @@ pub enum FilterOperator {
 pub struct AggregationConfig {
     pub group_by: Vec<String>,
     pub aggregates: Vec<AggregateFunction>,
+    /// Filters applied to each result group (fields are aliases or group_by names)
+    pub having: Option<Vec<FilterConfig>>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -81,6 +258,52 @@ pub enum AggregateFunctionType {
     Max,
     Count,
     CountDistinct,
+    Median,
+    /// Percentile on a 0-100 scale, linearly interpolated
+    Percentile(f64),
+    /// Population variance (divides by n)
+    Variance,
+    /// Population standard deviation (divides by n)
+    StdDev,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CacheStats {
+    pub hits: usize,
+    pub misses: usize,
+    pub entries: usize,
+    pub max_entries: usize,
+}
+
+/// One JSON Schema failure, located by a JSON pointer into the instance
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SchemaViolation {
+    pub path: String,
+    pub message: String,
+}
+
+fn gunzip(compressed_data: &[u8]) -> Result<Vec<u8>, TransformerError> {
+    let mut decoder = GzDecoder::new(Vec::new());
+    decoder.write_all(compressed_data)
+        .map_err(|e| TransformerError::compression(format!("Decompression failed: {}", e)))?;
+
+    decoder.finish()
+        .map_err(|e| TransformerError::compression(format!("Decompression finalization failed: {}", e)))
+}
+
+fn compile_schema(schema: &Value) -> Result<jsonschema::Validator, TransformerError> {
+    jsonschema::validator_for(schema)
+        .map_err(|e| TransformerError::validation(format!("Invalid schema: {}", e)).with_field("schema"))
+}
+
+fn schema_violations(validator: &jsonschema::Validator, instance: &Value) -> Vec<SchemaViolation> {
+    validator
+        .iter_errors(instance)
+        .map(|e| SchemaViolation {
+            path: e.instance_path.to_string(),
+            message: e.to_string(),
+        })
+        .collect()
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -91,10 +314,236 @@ pub struct BatchResult {
     pub time_ms: f64,
 }
 
+/// Parse an RFC 3339, "YYYY-MM-DDTHH:MM:SS" or "YYYY-MM-DD" date as UTC
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok()
+        .or_else(|| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S").ok().map(|dt| dt.and_utc()))
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .map(|dt| dt.and_utc())
+        })
+}
+
+/// Format with a strftime-style format, rejecting invalid specifiers rather
+/// than panicking on them
+fn format_date(date: &DateTime<Utc>, format: &str) -> Result<String, String> {
+    let items: Vec<Item> = StrftimeItems::new(format).collect();
+    if items.contains(&Item::Error) {
+        return Err(format!("Invalid date format: {}", format));
+    }
+    Ok(date.format_with_items(items.into_iter()).to_string())
+}
+
+// Dot-notation field paths ("address.postcode"). A key that exists verbatim
+// wins over descending, so dotted top-level keys keep working.
+
+fn get_path<'a>(obj: &'a Map<String, Value>, path: &str) -> Option<&'a Value> {
+    if let Some(value) = obj.get(path) {
+        return Some(value);
+    }
+    let (head, rest) = path.split_once('.')?;
+    get_path(obj.get(head)?.as_object()?, rest)
+}
+
+fn get_path_mut<'a>(obj: &'a mut Map<String, Value>, path: &str) -> Option<&'a mut Value> {
+    if obj.contains_key(path) {
+        return obj.get_mut(path);
+    }
+    let (head, rest) = path.split_once('.')?;
+    get_path_mut(obj.get_mut(head)?.as_object_mut()?, rest)
+}
+
+fn remove_path(obj: &mut Map<String, Value>, path: &str) -> Option<Value> {
+    if obj.contains_key(path) {
+        return obj.remove(path);
+    }
+    let (head, rest) = path.split_once('.')?;
+    remove_path(obj.get_mut(head)?.as_object_mut()?, rest)
+}
+
+/// Insert at a path, creating intermediate objects (and replacing non-object
+/// intermediates) as needed
+fn insert_path(obj: &mut Map<String, Value>, path: &str, value: Value) {
+    match path.split_once('.') {
+        Some((head, rest)) if !obj.contains_key(path) => {
+            let child = obj.entry(head.to_string()).or_insert_with(|| Value::Object(Map::new()));
+            if !child.is_object() {
+                *child = Value::Object(Map::new());
+            }
+            if let Value::Object(child_obj) = child {
+                insert_path(child_obj, rest, value);
+            }
+        }
+        _ => {
+            obj.insert(path.to_string(), value);
+        }
+    }
+}
+
+// Borrowed JSON value hashed structurally, so distinct counts don't need
+// an allocated string key per item
+struct DistinctKey<'a>(&'a Value);
+
+impl PartialEq for DistinctKey<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for DistinctKey<'_> {}
+
+impl Hash for DistinctKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_value(self.0, state);
+    }
+}
+
+fn hash_value<H: Hasher>(value: &Value, state: &mut H) {
+    match value {
+        Value::Null => 0u8.hash(state),
+        Value::Bool(b) => {
+            1u8.hash(state);
+            b.hash(state);
+        }
+        Value::Number(n) => {
+            2u8.hash(state);
+            if let Some(u) = n.as_u64() {
+                u.hash(state);
+            } else if let Some(i) = n.as_i64() {
+                i.hash(state);
+            } else if let Some(f) = n.as_f64() {
+                // -0.0 == 0.0, so they must hash the same
+                if f == 0.0 { 0u64.hash(state) } else { f.to_bits().hash(state) }
+            }
+        }
+        Value::String(s) => {
+            3u8.hash(state);
+            s.hash(state);
+        }
+        Value::Array(items) => {
+            4u8.hash(state);
+            items.len().hash(state);
+            for item in items {
+                hash_value(item, state);
+            }
+        }
+        // Key order isn't guaranteed to agree with equality, so only the size is hashed
+        Value::Object(obj) => {
+            5u8.hash(state);
+            obj.len().hash(state);
+        }
+    }
+}
+
+// Running state for one aggregate function over a group
+enum Accumulator<'a> {
+    Count(usize),
+    Sum(f64),
+    Average { sum: f64, count: usize },
+    Min(f64),
+    Max(f64),
+    CountDistinct(AHashSet<DistinctKey<'a>>),
+    Percentile { values: Vec<f64>, percentile: f64 },
+    // Welford's running mean and sum of squared deviations
+    Dispersion { count: usize, mean: f64, m2: f64, std_dev: bool },
+}
+
+impl<'a> Accumulator<'a> {
+    fn new(function: &AggregateFunctionType) -> Result<Self, String> {
+        Ok(match function {
+            AggregateFunctionType::Count => Accumulator::Count(0),
+            AggregateFunctionType::Sum => Accumulator::Sum(0.0),
+            AggregateFunctionType::Average => Accumulator::Average { sum: 0.0, count: 0 },
+            AggregateFunctionType::Min => Accumulator::Min(f64::INFINITY),
+            AggregateFunctionType::Max => Accumulator::Max(f64::NEG_INFINITY),
+            AggregateFunctionType::CountDistinct => Accumulator::CountDistinct(AHashSet::new()),
+            AggregateFunctionType::Median => Accumulator::Percentile { values: Vec::new(), percentile: 50.0 },
+            AggregateFunctionType::Percentile(p) => {
+                if !(0.0..=100.0).contains(p) {
+                    return Err(format!("Percentile must be between 0 and 100, got {}", p));
+                }
+                Accumulator::Percentile { values: Vec::new(), percentile: *p }
+            }
+            AggregateFunctionType::Variance => Accumulator::Dispersion { count: 0, mean: 0.0, m2: 0.0, std_dev: false },
+            AggregateFunctionType::StdDev => Accumulator::Dispersion { count: 0, mean: 0.0, m2: 0.0, std_dev: true },
+        })
+    }
+
+    fn push(&mut self, field_value: Option<&'a Value>) {
+        let number = field_value.and_then(Value::as_f64);
+
+        match (self, number) {
+            (Accumulator::Count(count), _) => *count += 1,
+            (Accumulator::CountDistinct(unique), _) => {
+                if let Some(v) = field_value {
+                    unique.insert(DistinctKey(v));
+                }
+            }
+            (Accumulator::Sum(sum), Some(n)) => *sum += n,
+            (Accumulator::Average { sum, count }, Some(n)) => {
+                *sum += n;
+                *count += 1;
+            }
+            (Accumulator::Min(min), Some(n)) => *min = min.min(n),
+            (Accumulator::Max(max), Some(n)) => *max = max.max(n),
+            (Accumulator::Percentile { values, .. }, Some(n)) => values.push(n),
+            (Accumulator::Dispersion { count, mean, m2, .. }, Some(n)) => {
+                *count += 1;
+                let delta = n - *mean;
+                *mean += delta / *count as f64;
+                *m2 += delta * (n - *mean);
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(self) -> Value {
+        match self {
+            Accumulator::Count(count) => Value::from(count),
+            Accumulator::Sum(sum) => Value::from(sum),
+            Accumulator::Average { count: 0, .. } => Value::Null,
+            Accumulator::Average { sum, count } => Value::from(sum / count as f64),
+            Accumulator::Min(min) if min == f64::INFINITY => Value::Null,
+            Accumulator::Max(max) if max == f64::NEG_INFINITY => Value::Null,
+            Accumulator::Min(value) | Accumulator::Max(value) => Value::from(value),
+            Accumulator::CountDistinct(unique) => Value::from(unique.len()),
+            Accumulator::Percentile { values, .. } if values.is_empty() => Value::Null,
+            Accumulator::Percentile { mut values, percentile } => {
+                values.sort_by(|a, b| a.total_cmp(b));
+
+                // Same linear interpolation as the stats engine
+                let index = (values.len() as f64 - 1.0) * (percentile / 100.0);
+                let lower = index.floor() as usize;
+                let upper = index.ceil() as usize;
+                let weight = index - lower as f64;
+                Value::from(values[lower] * (1.0 - weight) + values[upper] * weight)
+            }
+            Accumulator::Dispersion { count: 0, .. } => Value::Null,
+            Accumulator::Dispersion { count, m2, std_dev, .. } => {
+                let variance = m2 / count as f64;
+                Value::from(if std_dev { variance.sqrt() } else { variance })
+            }
+        }
+    }
+}
+
+// SHA-256 of a transformBatch call's (data_json, config_json)
+type CacheKey = [u8; 32];
+
 #[wasm_bindgen]
 pub struct DataTransformer {
     compression_level: u32,
-    cache: AHashMap<String, Vec<u8>>,
+    cache: AHashMap<CacheKey, Vec<u8>>,
+    // Insertion order of cache keys, oldest first, for eviction
+    cache_order: VecDeque<CacheKey>,
+    max_cache_entries: usize,
+    cache_hits: usize,
+    cache_misses: usize,
 }
 
 #[wasm_bindgen]
@@ -105,6 +554,10 @@ impl DataTransformer {
         DataTransformer {
             compression_level: 6,
             cache: AHashMap::new(),
+            cache_order: VecDeque::new(),
+            max_cache_entries: DEFAULT_MAX_CACHE_ENTRIES,
+            cache_hits: 0,
+            cache_misses: 0,
         }
     }
 
@@ -113,21 +566,21 @@ impl DataTransformer {
     pub fn parse_json(&self, json_str: &str) -> Result<String, JsValue> {
         let mut json_string = json_str.to_string();
         let value: Value = simd_json::from_str(&mut json_string)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse JSON: {}", e)))?;
+            .map_err(|e| TransformerError::parse(format!("Failed to parse JSON: {}", e)).with_field("json_str"))?;
 
         serde_json::to_string(&value)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+            .map_err(|e| TransformerError::serialization(format!("Serialization error: {}", e)).into())
     }
 
     /// Serialize to JSON with SIMD optimization
     #[wasm_bindgen(js_name = serializeJson)]
     pub fn serialize_json(&self, data: &str) -> Result<String, JsValue> {
         let value: Value = serde_json::from_str(data)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse input: {}", e)))?;
+            .map_err(|e| TransformerError::parse(format!("Failed to parse input: {}", e)).with_field("data"))?;
 
         // Use compact serialization for smaller output
         serde_json::to_string(&value)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+            .map_err(|e| TransformerError::serialization(format!("Serialization error: {}", e)).into())
     }
 
     /// Compress data using gzip
@@ -135,24 +588,19 @@ impl DataTransformer {
     pub fn compress(&self, data: &str) -> Result<Vec<u8>, JsValue> {
         let mut encoder = GzEncoder::new(Vec::new(), Compression::new(self.compression_level));
         encoder.write_all(data.as_bytes())
-            .map_err(|e| JsValue::from_str(&format!("Compression failed: {}", e)))?;
+            .map_err(|e| TransformerError::compression(format!("Compression failed: {}", e)))?;
 
         encoder.finish()
-            .map_err(|e| JsValue::from_str(&format!("Compression finalization failed: {}", e)))
+            .map_err(|e| TransformerError::compression(format!("Compression finalization failed: {}", e)).into())
     }
 
     /// Decompress gzip data
     #[wasm_bindgen(js_name = decompress)]
     pub fn decompress(&self, compressed_data: &[u8]) -> Result<String, JsValue> {
-        let mut decoder = GzDecoder::new(Vec::new());
-        decoder.write_all(compressed_data)
-            .map_err(|e| JsValue::from_str(&format!("Decompression failed: {}", e)))?;
-
-        let decompressed = decoder.finish()
-            .map_err(|e| JsValue::from_str(&format!("Decompression finalization failed: {}", e)))?;
+        let decompressed = gunzip(compressed_data)?;
 
         String::from_utf8(decompressed)
-            .map_err(|e| JsValue::from_str(&format!("Invalid UTF-8 in decompressed data: {}", e)))
+            .map_err(|e| TransformerError::decode(format!("Invalid UTF-8 in decompressed data: {}", e)).into())
     }
 
     /// Compress using LZ4
@@ -165,44 +613,162 @@ impl DataTransformer {
     #[wasm_bindgen(js_name = decompressLZ4)]
     pub fn decompress_lz4(&self, compressed_data: &[u8]) -> Result<String, JsValue> {
         let decompressed = lz4_flex::decompress_size_prepended(compressed_data)
-            .map_err(|e| JsValue::from_str(&format!("LZ4 decompression failed: {}", e)))?;
+            .map_err(|e| TransformerError::compression(format!("LZ4 decompression failed: {}", e)))?;
+
+        String::from_utf8(decompressed)
+            .map_err(|e| TransformerError::decode(format!("Invalid UTF-8 in decompressed data: {}", e)).into())
+    }
+
+    /// Compress using zstd, with the uncompressed size prepended like the LZ4 helpers
+    #[wasm_bindgen(js_name = compressZstd)]
+    pub fn compress_zstd(&self, data: &str) -> Result<Vec<u8>, JsValue> {
+        let max_level = *zstd::compression_level_range().end();
+        let level = (self.compression_level as i32).clamp(1, max_level);
+
+        let compressed = zstd::bulk::compress(data.as_bytes(), level)
+            .map_err(|e| TransformerError::compression(format!("Zstd compression failed: {}", e)))?;
+
+        let mut output = Vec::with_capacity(compressed.len() + 4);
+        output.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        output.extend_from_slice(&compressed);
+        Ok(output)
+    }
+
+    /// Decompress size-prepended zstd data
+    #[wasm_bindgen(js_name = decompressZstd)]
+    pub fn decompress_zstd(&self, compressed_data: &[u8]) -> Result<String, JsValue> {
+        if compressed_data.len() < 4 {
+            return Err(TransformerError::compression("Zstd decompression failed: missing size prefix").into());
+        }
+
+        let (size_bytes, frame) = compressed_data.split_at(4);
+        let size = u32::from_le_bytes([size_bytes[0], size_bytes[1], size_bytes[2], size_bytes[3]]) as usize;
+
+        let decompressed = zstd::bulk::decompress(frame, size)
+            .map_err(|e| TransformerError::compression(format!("Zstd decompression failed: {}", e)))?;
 
         String::from_utf8(decompressed)
-            .map_err(|e| JsValue::from_str(&format!("Invalid UTF-8 in decompressed data: {}", e)))
+            .map_err(|e| TransformerError::decode(format!("Invalid UTF-8 in decompressed data: {}", e)).into())
+    }
+
+    /// Decompress gzip, zstd or LZ4 data, detecting the format from its leading bytes
+    #[wasm_bindgen(js_name = decompressAuto)]
+    pub fn decompress_auto(&self, compressed_data: &[u8]) -> Result<String, JsValue> {
+        // Size-prefixed zstd (compressZstd) is checked first since its magic is the most specific
+        if compressed_data.len() >= 8 && compressed_data[4..8] == ZSTD_MAGIC {
+            return self.decompress_zstd(compressed_data);
+        }
+
+        // An LZ4 size prefix can spell the gzip magic too, so a failed gzip
+        // decode falls through to LZ4 and is only reported if that fails
+        let mut gzip_error = None;
+        if compressed_data.starts_with(&GZIP_MAGIC) {
+            match gunzip(compressed_data) {
+                Ok(decompressed) => {
+                    return String::from_utf8(decompressed)
+                        .map_err(|e| TransformerError::decode(format!("Invalid UTF-8 in decompressed data: {}", e)).into());
+                }
+                Err(e) => gzip_error = Some(e),
+            }
+        }
+
+        if compressed_data.starts_with(&ZSTD_MAGIC) {
+            let decompressed = zstd::stream::decode_all(compressed_data)
+                .map_err(|e| TransformerError::compression(format!("Zstd decompression failed: {}", e)))?;
+            return String::from_utf8(decompressed)
+                .map_err(|e| TransformerError::decode(format!("Invalid UTF-8 in decompressed data: {}", e)).into());
+        }
+
+        // LZ4 has no magic bytes, only a little-endian size prefix that must be plausible
+        if compressed_data.len() >= 4 {
+            let size = u32::from_le_bytes([compressed_data[0], compressed_data[1], compressed_data[2], compressed_data[3]]) as usize;
+            let payload_len = compressed_data.len() - 4;
+            if size <= payload_len.saturating_mul(LZ4_MAX_RATIO) + 16 {
+                if let Ok(decompressed) = lz4_flex::decompress_size_prepended(compressed_data) {
+                    return String::from_utf8(decompressed)
+                        .map_err(|e| TransformerError::decode(format!("Invalid UTF-8 in decompressed data: {}", e)).into());
+                }
+            }
+        }
+
+        if let Some(e) = gzip_error {
+            return Err(e.into());
+        }
+
+        let leading: Vec<String> = compressed_data.iter().take(4).map(|b| format!("{:02x}", b)).collect();
+        Err(TransformerError::compression(format!(
+            "Unrecognized compression format (leading bytes: {}); expected gzip, zstd or size-prefixed LZ4",
+            if leading.is_empty() { "none".to_string() } else { leading.join(" ") }
+        )).with_field("compressed_data").into())
     }
 
-    /// Transform a batch of JSON objects
+    /// Transform a batch of JSON objects, returning the cached output for a
+    /// repeated (data, config) pair. A cached output is replayed as-is, so
+    /// its metadata (including time_ms) is that of the original run
     #[wasm_bindgen(js_name = transformBatch)]
-    pub fn transform_batch(&self, data_json: &str, config_json: &str) -> Result<String, JsValue> {
+    pub fn transform_batch(&mut self, data_json: &str, config_json: &str) -> Result<String, JsValue> {
+        let key = Self::cache_key(data_json, config_json);
+
+        if let Some(cached) = self.cache.get(&key) {
+            self.cache_hits += 1;
+            return String::from_utf8(cached.clone())
+                .map_err(|e| TransformerError::decode(format!("Invalid UTF-8 in cached data: {}", e)).into());
+        }
+
+        self.cache_misses += 1;
+        let output = self.run_transform_batch(data_json, config_json)?;
+        self.cache_insert(key, output.as_bytes().to_vec());
+
+        Ok(output)
+    }
+
+    fn cache_key(data_json: &str, config_json: &str) -> CacheKey {
+        // Length-prefix the data so ("ab", "c") and ("a", "bc") differ
+        let mut hasher = Sha256::new();
+        hasher.update((data_json.len() as u64).to_le_bytes());
+        hasher.update(data_json.as_bytes());
+        hasher.update(config_json.as_bytes());
+        hasher.finalize().into()
+    }
+
+    fn cache_insert(&mut self, key: CacheKey, output: Vec<u8>) {
+        if self.max_cache_entries == 0 {
+            return;
+        }
+
+        self.evict_cache_to(self.max_cache_entries - 1);
+        self.cache_order.push_back(key);
+        self.cache.insert(key, output);
+    }
+
+    /// Drop the oldest cache entries until at most `limit` remain
+    fn evict_cache_to(&mut self, limit: usize) {
+        while self.cache.len() > limit {
+            match self.cache_order.pop_front() {
+                Some(oldest) => {
+                    self.cache.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn run_transform_batch(&self, data_json: &str, config_json: &str) -> Result<String, TransformerError> {
         let start = web_sys::window()
             .and_then(|w| w.performance())
             .map(|p| p.now())
             .unwrap_or(0.0);
 
-        let mut data: Vec<Value> = serde_json::from_str(data_json)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse data: {}", e)))?;
+        let data: Vec<Value> = serde_json::from_str(data_json)
+            .map_err(|e| TransformerError::parse(format!("Failed to parse data: {}", e)).with_field("data_json"))?;
 
         let config: TransformConfig = serde_json::from_str(config_json)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse config: {}", e)))?;
+            .map_err(|e| TransformerError::parse(format!("Failed to parse config: {}", e)).with_field("config_json"))?;
 
         let initial_count = data.len();
         let mut errors = Vec::new();
-
-        // Apply filters
-        if let Some(filters) = &config.filters {
-            data = data.into_iter()
-                .filter(|item| self.apply_filters(item, filters))
-                .collect();
-        }
-
-        // Transform each item
-        let mut transformed: Vec<Value> = Vec::new();
-        for item in data {
-            match self.transform_item(item, &config) {
-                Ok(transformed_item) => transformed.push(transformed_item),
-                Err(e) => errors.push(format!("Transform error: {}", e)),
-            }
-        }
+        let items = data.into_iter().enumerate().collect();
+        let transformed = self.run_pipeline(items, &config, "index", &mut errors)?;
 
         let end = web_sys::window()
             .and_then(|w| w.performance())
@@ -228,7 +794,118 @@ impl DataTransformer {
         };
 
         serde_json::to_string(&output)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+            .map_err(|e| TransformerError::serialization(format!("Serialization error: {}", e)))
+    }
+
+    /// Transform newline-delimited JSON. Blank lines are skipped and lines
+    /// that fail to parse are reported in metadata.errors by line number.
+    #[wasm_bindgen(js_name = transformBatchNdjson)]
+    pub fn transform_batch_ndjson(&self, data_ndjson: &str, config_json: &str) -> Result<String, JsValue> {
+        let start = web_sys::window()
+            .and_then(|w| w.performance())
+            .map(|p| p.now())
+            .unwrap_or(0.0);
+
+        let config: TransformConfig = serde_json::from_str(config_json)
+            .map_err(|e| TransformerError::parse(format!("Failed to parse config: {}", e)).with_field("config_json"))?;
+
+        let mut errors = Vec::new();
+        let mut items = Vec::new();
+        for (index, line) in data_ndjson.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str::<Value>(line) {
+                Ok(item) => items.push((index + 1, item)),
+                Err(e) => errors.push(format!("Parse error at line {}: {}", index + 1, e)),
+            }
+        }
+
+        let initial_count = items.len();
+        let transformed = self.run_pipeline(items, &config, "line", &mut errors)?;
+
+        let mut data = String::new();
+        for item in &transformed {
+            let line = serde_json::to_string(item)
+                .map_err(|e| TransformerError::serialization(format!("Serialization error: {}", e)))?;
+            data.push_str(&line);
+            data.push('\n');
+        }
+
+        let end = web_sys::window()
+            .and_then(|w| w.performance())
+            .map(|p| p.now())
+            .unwrap_or(0.0);
+
+        #[derive(Serialize)]
+        struct NdjsonTransformResult {
+            data: String,
+            metadata: BatchResult,
+        }
+
+        let output = NdjsonTransformResult {
+            data,
+            metadata: BatchResult {
+                processed: transformed.len(),
+                filtered: initial_count - transformed.len(),
+                errors,
+                time_ms: end - start,
+            },
+        };
+
+        serde_json::to_string(&output)
+            .map_err(|e| TransformerError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
+    /// Validate, filter, then transform items tagged with their source
+    /// position. In strict mode the first schema violation or transform
+    /// error aborts with "at <unit> <pos>".
+    fn run_pipeline(
+        &self,
+        items: Vec<(usize, Value)>,
+        config: &TransformConfig,
+        unit: &str,
+        errors: &mut Vec<String>,
+    ) -> Result<Vec<Value>, TransformerError> {
+        let strict = config.strict.unwrap_or(false);
+        let validator = config.schema.as_ref().map(compile_schema).transpose()?;
+        let mut transformed = Vec::new();
+
+        for (position, item) in items {
+            if let Some(validator) = &validator {
+                let violations = schema_violations(validator, &item);
+                if !violations.is_empty() {
+                    let details: Vec<String> = violations
+                        .iter()
+                        .map(|v| format!("{} at '{}'", v.message, v.path))
+                        .collect();
+                    let message = format!("Schema violation at {} {}: {}", unit, position, details.join("; "));
+                    if strict {
+                        return Err(TransformerError::validation(message));
+                    }
+                    errors.push(message);
+                    continue;
+                }
+            }
+
+            if let Some(filters) = &config.filters {
+                if !self.apply_filters(&item, filters) {
+                    continue;
+                }
+            }
+
+            match self.transform_item(item, config) {
+                Ok(transformed_item) => transformed.push(transformed_item),
+                Err(e) if strict => {
+                    return Err(TransformerError::transform(format!("Transform error at {} {}: {}", unit, position, e)));
+                }
+                Err(e) => errors.push(format!("Transform error: {}", e)),
+            }
+        }
+
+        Ok(transformed)
     }
 
     fn apply_filters(&self, item: &Value, filters: &[FilterConfig]) -> bool {
@@ -241,16 +918,17 @@ impl DataTransformer {
     }
 
     fn evaluate_filter(&self, item: &Value, filter: &FilterConfig) -> bool {
-        let field_value = item.get(&filter.field);
+        let field_value = item.as_object().and_then(|obj| get_path(obj, &filter.field));
         if field_value.is_none() {
             return false;
         }
 
         let field_value = field_value.unwrap();
+        let case_insensitive = filter.case_insensitive.unwrap_or(false);
 
         match filter.operator {
-            FilterOperator::Equals => field_value == &filter.value,
-            FilterOperator::NotEquals => field_value != &filter.value,
+            FilterOperator::Equals => Self::values_equal(field_value, &filter.value, case_insensitive),
+            FilterOperator::NotEquals => !Self::values_equal(field_value, &filter.value, case_insensitive),
             FilterOperator::GreaterThan => {
                 if let (Some(a), Some(b)) = (field_value.as_f64(), filter.value.as_f64()) {
                     a > b
@@ -280,25 +958,13 @@ impl DataTransformer {
                 }
             }
             FilterOperator::Contains => {
-                if let (Some(a), Some(b)) = (field_value.as_str(), filter.value.as_str()) {
-                    a.contains(b)
-                } else {
-                    false
-                }
+                Self::compare_strings(field_value, &filter.value, case_insensitive, |a, b| a.contains(b))
             }
             FilterOperator::StartsWith => {
-                if let (Some(a), Some(b)) = (field_value.as_str(), filter.value.as_str()) {
-                    a.starts_with(b)
-                } else {
-                    false
-                }
+                Self::compare_strings(field_value, &filter.value, case_insensitive, |a, b| a.starts_with(b))
             }
             FilterOperator::EndsWith => {
-                if let (Some(a), Some(b)) = (field_value.as_str(), filter.value.as_str()) {
-                    a.ends_with(b)
-                } else {
-                    false
-                }
+                Self::compare_strings(field_value, &filter.value, case_insensitive, |a, b| a.ends_with(b))
             }
             FilterOperator::In => {
                 if let Some(array) = filter.value.as_array() {
@@ -317,21 +983,38 @@ impl DataTransformer {
         }
     }
 
+    fn values_equal(a: &Value, b: &Value, case_insensitive: bool) -> bool {
+        match (a.as_str(), b.as_str()) {
+            (Some(a), Some(b)) if case_insensitive => a.to_lowercase() == b.to_lowercase(),
+            _ => a == b,
+        }
+    }
+
+    fn compare_strings(a: &Value, b: &Value, case_insensitive: bool, op: impl Fn(&str, &str) -> bool) -> bool {
+        match (a.as_str(), b.as_str()) {
+            (Some(a), Some(b)) if case_insensitive => op(&a.to_lowercase(), &b.to_lowercase()),
+            (Some(a), Some(b)) => op(a, b),
+            _ => false,
+        }
+    }
+
     fn transform_item(&self, mut item: Value, config: &TransformConfig) -> Result<Value, String> {
         if let Some(obj) = item.as_object_mut() {
             // Field removal
             if let Some(fields) = &config.fields_to_remove {
                 for field in fields {
-                    obj.remove(field);
+                    remove_path(obj, field);
                 }
             }
 
             // Field keeping (remove all others)
             if let Some(fields) = &config.fields_to_keep {
-                let mut new_obj = serde_json::Map::new();
+                let mut new_obj = Map::new();
                 for field in fields {
                     if let Some(value) = obj.get(field) {
                         new_obj.insert(field.clone(), value.clone());
+                    } else if let Some(value) = get_path(obj, field) {
+                        insert_path(&mut new_obj, field, value.clone());
                     }
                 }
                 *obj = new_obj;
@@ -339,19 +1022,39 @@ impl DataTransformer {
 
             // Field mappings (rename fields)
             if let Some(mappings) = &config.field_mappings {
-                for (old_name, new_name) in mappings {
-                    if let Some(value) = obj.remove(old_name) {
-                        obj.insert(new_name.clone(), value);
-                    }
+                let renamed: Vec<(&String, Value)> = mappings
+                    .iter()
+                    .filter_map(|(old_name, new_name)| remove_path(obj, old_name).map(|value| (new_name, value)))
+                    .collect();
+                for (new_name, value) in renamed {
+                    insert_path(obj, new_name, value);
                 }
             }
 
             // Field transformations
             if let Some(transforms) = &config.field_transforms {
-                for (field, transform_type) in transforms {
-                    if let Some(value) = obj.get_mut(field) {
-                        *value = self.apply_transform(value.clone(), transform_type)?;
-                    }
+                for (field, field_transform) in transforms {
+                    let field = match config.field_mappings.as_ref().and_then(|m| m.get(field)) {
+                        Some(new_name) if get_path(obj, field).is_none() => new_name,
+                        _ => field,
+                    };
+                    self.transform_field(obj, field, field_transform)?;
+                }
+            }
+        }
+
+        // Conditional transforms, matched against the whole item
+        if let Some(conditionals) = &config.conditional_transforms {
+            for conditional in conditionals {
+                let field_transform = if self.evaluate_filter(&item, &conditional.when) {
+                    &conditional.transform
+                } else if let Some(otherwise) = &conditional.otherwise {
+                    otherwise
+                } else {
+                    continue;
+                };
+                if let Some(obj) = item.as_object_mut() {
+                    self.transform_field(obj, &conditional.field, field_transform)?;
                 }
             }
         }
@@ -359,6 +1062,34 @@ impl DataTransformer {
         Ok(item)
     }
 
+    fn transform_field(&self, obj: &mut Map<String, Value>, field: &str, field_transform: &FieldTransform) -> Result<(), String> {
+        let in_field = |e: String| format!("{}: {}", field, e);
+        if let Some(value) = get_path_mut(obj, field) {
+            *value = self.apply_transforms(value.clone(), field_transform).map_err(in_field)?;
+        } else if field_transform.fills_missing() {
+            let value = self.apply_transforms(Value::Null, field_transform).map_err(in_field)?;
+            insert_path(obj, field, value);
+        }
+        Ok(())
+    }
+
+    /// Keep an integer input an integer when the arithmetic result is integral
+    fn numeric_result(input: &Value, result: f64) -> Value {
+        let integral_input = input.is_i64() || input.is_u64();
+        if integral_input && result.fract() == 0.0 && result >= i64::MIN as f64 && result < i64::MAX as f64 {
+            Value::from(result as i64)
+        } else {
+            Value::from(result)
+        }
+    }
+
+    fn apply_transforms(&self, value: Value, field_transform: &FieldTransform) -> Result<Value, String> {
+        field_transform
+            .stages()
+            .iter()
+            .try_fold(value, |value, transform| self.apply_transform(value, transform))
+    }
+
     fn apply_transform(&self, value: Value, transform: &TransformType) -> Result<Value, String> {
         match transform {
             TransformType::Lowercase => {
@@ -385,21 +1116,21 @@ impl DataTransformer {
             TransformType::Round(decimals) => {
                 if let Some(n) = value.as_f64() {
                     let multiplier = 10_f64.powi(*decimals as i32);
-                    Ok(Value::from((n * multiplier).round() / multiplier))
+                    Ok(Self::numeric_result(&value, (n * multiplier).round() / multiplier))
                 } else {
                     Ok(value)
                 }
             }
             TransformType::Multiply(factor) => {
                 if let Some(n) = value.as_f64() {
-                    Ok(Value::from(n * factor))
+                    Ok(Self::numeric_result(&value, n * factor))
                 } else {
                     Ok(value)
                 }
             }
             TransformType::Add(amount) => {
                 if let Some(n) = value.as_f64() {
-                    Ok(Value::from(n + amount))
+                    Ok(Self::numeric_result(&value, n + amount))
                 } else {
                     Ok(value)
                 }
@@ -411,16 +1142,22 @@ impl DataTransformer {
                     Ok(value)
                 }
             }
-            TransformType::Hash => {
+            TransformType::Hash(algo) => {
                 let s = match value {
                     Value::String(s) => s,
                     _ => value.to_string(),
                 };
-                // Simple hash function
-                let hash = s.bytes().fold(0u32, |acc, b| {
-                    acc.wrapping_mul(31).wrapping_add(b as u32)
-                });
-                Ok(Value::String(format!("{:x}", hash)))
+                let hash = match algo {
+                    HashAlgo::Sha256 => format!("{:x}", Sha256::digest(s.as_bytes())),
+                    HashAlgo::Blake3 => blake3::hash(s.as_bytes()).to_hex().to_string(),
+                    HashAlgo::Legacy => {
+                        let hash = s.bytes().fold(0u32, |acc, b| {
+                            acc.wrapping_mul(31).wrapping_add(b as u32)
+                        });
+                        format!("{:x}", hash)
+                    }
+                };
+                Ok(Value::String(hash))
             }
             TransformType::Truncate(max_len) => {
                 if let Some(s) = value.as_str() {
@@ -433,48 +1170,135 @@ impl DataTransformer {
                     Ok(value)
                 }
             }
-            TransformType::DateFormat(_format) => {
-                // Simplified date formatting
-                Ok(value)
+            TransformType::DateFormat(format) => match &value {
+                Value::String(s) => {
+                    let date = parse_date(s).ok_or_else(|| format!("Cannot parse '{}' as a date", s))?;
+                    format_date(&date, format).map(Value::String)
+                }
+                _ => Ok(value),
+            },
+            TransformType::ToNumber => match &value {
+                Value::String(s) => {
+                    let s = s.trim();
+                    if let Ok(n) = s.parse::<i64>() {
+                        Ok(Value::from(n))
+                    } else {
+                        Ok(s.parse::<f64>()
+                            .ok()
+                            .and_then(serde_json::Number::from_f64)
+                            .map_or(value.clone(), Value::Number))
+                    }
+                }
+                Value::Bool(b) => Ok(Value::from(*b as i64)),
+                _ => Ok(value),
+            },
+            TransformType::ToString => match &value {
+                Value::Number(n) => Ok(Value::String(n.to_string())),
+                Value::Bool(b) => Ok(Value::String(b.to_string())),
+                _ => Ok(value),
+            },
+            TransformType::ToBool => {
+                let parsed = match &value {
+                    Value::String(s) => match s.trim().to_lowercase().as_str() {
+                        "true" | "1" => Some(true),
+                        "false" | "0" => Some(false),
+                        _ => None,
+                    },
+                    Value::Number(n) => match n.as_i64() {
+                        Some(1) => Some(true),
+                        Some(0) => Some(false),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                Ok(parsed.map_or(value, Value::Bool))
+            }
+            TransformType::Split(delimiter) => {
+                if let Some(s) = value.as_str() {
+                    Ok(Value::Array(
+                        s.split(delimiter.as_str())
+                            .map(str::trim)
+                            .filter(|part| !part.is_empty())
+                            .map(|part| Value::String(part.to_string()))
+                            .collect(),
+                    ))
+                } else {
+                    Ok(value)
+                }
             }
+            TransformType::Join(delimiter) => {
+                if let Some(items) = value.as_array() {
+                    let parts: Vec<String> = items
+                        .iter()
+                        .filter(|item| !item.is_null())
+                        .map(|item| match item {
+                            Value::String(s) => s.clone(),
+                            other => other.to_string(),
+                        })
+                        .collect();
+                    Ok(Value::String(parts.join(delimiter)))
+                } else {
+                    Ok(value)
+                }
+            }
+            TransformType::DefaultIfEmpty(default) => match &value {
+                Value::Null => Ok(default.clone()),
+                Value::String(s) if s.trim().is_empty() => Ok(default.clone()),
+                _ => Ok(value),
+            },
         }
     }
 
+    /// Validate a JSON document against a JSON Schema, returning every
+    /// violation with the JSON pointer of the offending value
+    #[wasm_bindgen(js_name = validateAgainstSchema)]
+    pub fn validate_against_schema(&self, data_json: &str, schema_json: &str) -> Result<String, JsValue> {
+        let data: Value = serde_json::from_str(data_json)
+            .map_err(|e| TransformerError::parse(format!("Failed to parse data: {}", e)).with_field("data_json"))?;
+        let schema: Value = serde_json::from_str(schema_json)
+            .map_err(|e| TransformerError::parse(format!("Failed to parse schema: {}", e)).with_field("schema_json"))?;
+
+        let validator = compile_schema(&schema).map_err(|e| e.with_field("schema_json"))?;
+
+        serde_json::to_string(&schema_violations(&validator, &data))
+            .map_err(|e| TransformerError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
     /// Stream-process large JSON arrays
     #[wasm_bindgen(js_name = streamProcess)]
     pub fn stream_process(&self, json_str: &str, chunk_size: usize, processor: &js_sys::Function) -> Result<String, JsValue> {
         let data: Vec<Value> = serde_json::from_str(json_str)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse JSON: {}", e)))?;
+            .map_err(|e| TransformerError::parse(format!("Failed to parse JSON: {}", e)).with_field("json_str"))?;
 
         let mut results = Vec::new();
         let this = JsValue::null();
 
         for chunk in data.chunks(chunk_size) {
             let chunk_json = serde_json::to_string(chunk)
-                .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))?;
+                .map_err(|e| TransformerError::serialization(format!("Serialization error: {}", e)))?;
 
             let js_chunk = JsValue::from_str(&chunk_json);
             let result = processor.call1(&this, &js_chunk)?;
 
             if let Some(result_str) = result.as_string() {
                 let processed: Vec<Value> = serde_json::from_str(&result_str)
-                    .map_err(|e| JsValue::from_str(&format!("Failed to parse processor result: {}", e)))?;
+                    .map_err(|e| TransformerError::parse(format!("Failed to parse processor result: {}", e)))?;
                 results.extend(processed);
             }
         }
 
         serde_json::to_string(&results)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+            .map_err(|e| TransformerError::serialization(format!("Serialization error: {}", e)).into())
     }
 
     /// Aggregate data
     #[wasm_bindgen(js_name = aggregate)]
     pub fn aggregate(&self, data_json: &str, config_json: &str) -> Result<String, JsValue> {
         let data: Vec<Value> = serde_json::from_str(data_json)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse data: {}", e)))?;
+            .map_err(|e| TransformerError::parse(format!("Failed to parse data: {}", e)).with_field("data_json"))?;
 
         let config: AggregationConfig = serde_json::from_str(config_json)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse config: {}", e)))?;
+            .map_err(|e| TransformerError::parse(format!("Failed to parse config: {}", e)).with_field("config_json"))?;
 
         // Group data
         let mut groups: AHashMap<Vec<String>, Vec<&Value>> = AHashMap::new();
@@ -496,7 +1320,7 @@ impl DataTransformer {
         let mut results = Vec::new();
 
         for (group_key, group_items) in groups {
-            let mut result_obj = serde_json::Map::new();
+            let mut result_obj = Map::new();
 
             // Add group keys
             for (i, field) in config.group_by.iter().enumerate() {
@@ -504,69 +1328,39 @@ impl DataTransformer {
             }
 
             // Calculate aggregates
-            for agg_func in &config.aggregates {
-                let value = self.calculate_aggregate(&group_items, &agg_func)?;
+            let values = self.calculate_aggregates(&group_items, &config.aggregates)?;
+            for (agg_func, value) in config.aggregates.iter().zip(values) {
                 result_obj.insert(agg_func.alias.clone(), value);
             }
 
-            results.push(Value::Object(result_obj));
+            let result = Value::Object(result_obj);
+            if let Some(having) = &config.having {
+                if !self.apply_filters(&result, having) {
+                    continue;
+                }
+            }
+
+            results.push(result);
         }
 
         serde_json::to_string(&results)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
-    }
-
-    fn calculate_aggregate(&self, items: &[&Value], agg_func: &AggregateFunction) -> Result<Value, String> {
-        match agg_func.function {
-            AggregateFunctionType::Count => Ok(Value::from(items.len())),
-            AggregateFunctionType::Sum => {
-                let sum: f64 = items.iter()
-                    .filter_map(|item| item.get(&agg_func.field))
-                    .filter_map(|v| v.as_f64())
-                    .sum();
-                Ok(Value::from(sum))
-            }
-            AggregateFunctionType::Average => {
-                let values: Vec<f64> = items.iter()
-                    .filter_map(|item| item.get(&agg_func.field))
-                    .filter_map(|v| v.as_f64())
-                    .collect();
-                if values.is_empty() {
-                    Ok(Value::Null)
-                } else {
-                    Ok(Value::from(values.iter().sum::<f64>() / values.len() as f64))
-                }
-            }
-            AggregateFunctionType::Min => {
-                let min = items.iter()
-                    .filter_map(|item| item.get(&agg_func.field))
-                    .filter_map(|v| v.as_f64())
-                    .fold(f64::INFINITY, |a, b| a.min(b));
-                if min == f64::INFINITY {
-                    Ok(Value::Null)
-                } else {
-                    Ok(Value::from(min))
-                }
-            }
-            AggregateFunctionType::Max => {
-                let max = items.iter()
-                    .filter_map(|item| item.get(&agg_func.field))
-                    .filter_map(|v| v.as_f64())
-                    .fold(f64::NEG_INFINITY, |a, b| a.max(b));
-                if max == f64::NEG_INFINITY {
-                    Ok(Value::Null)
-                } else {
-                    Ok(Value::from(max))
-                }
-            }
-            AggregateFunctionType::CountDistinct => {
-                let unique: AHashSet<String> = items.iter()
-                    .filter_map(|item| item.get(&agg_func.field))
-                    .map(|v| v.to_string())
-                    .collect();
-                Ok(Value::from(unique.len()))
+            .map_err(|e| TransformerError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
+    /// Compute every aggregate for a group in a single pass over its items
+    fn calculate_aggregates(&self, items: &[&Value], aggregates: &[AggregateFunction]) -> Result<Vec<Value>, String> {
+        let mut accumulators: Vec<Accumulator> = aggregates
+            .iter()
+            .map(|agg_func| Accumulator::new(&agg_func.function))
+            .collect::<Result<_, _>>()?;
+
+        for item in items {
+            for (accumulator, agg_func) in accumulators.iter_mut().zip(aggregates) {
+                accumulator.push(item.get(&agg_func.field));
             }
         }
+
+        Ok(accumulators.into_iter().map(Accumulator::finish).collect())
     }
 
     /// Set compression level (0-9)
@@ -579,6 +1373,28 @@ impl DataTransformer {
     #[wasm_bindgen(js_name = clearCache)]
     pub fn clear_cache(&mut self) {
         self.cache.clear();
+        self.cache_order.clear();
+    }
+
+    /// Set the maximum number of cached transformBatch results (0 disables caching)
+    #[wasm_bindgen(js_name = setMaxCacheEntries)]
+    pub fn set_max_cache_entries(&mut self, max_entries: usize) {
+        self.max_cache_entries = max_entries;
+        self.evict_cache_to(max_entries);
+    }
+
+    /// Get cache hit/miss counts and occupancy
+    #[wasm_bindgen(js_name = getCacheStats)]
+    pub fn get_cache_stats(&self) -> Result<String, JsValue> {
+        let stats = CacheStats {
+            hits: self.cache_hits,
+            misses: self.cache_misses,
+            entries: self.cache.len(),
+            max_entries: self.max_cache_entries,
+        };
+
+        serde_json::to_string(&stats)
+            .map_err(|e| TransformerError::serialization(format!("Serialization error: {}", e)).into())
     }
 }
 
@@ -591,5 +1407,278 @@ pub fn base64_encode(data: &[u8]) -> String {
 #[wasm_bindgen(js_name = base64Decode)]
 pub fn base64_decode(encoded: &str) -> Result<Vec<u8>, JsValue> {
     base64::Engine::decode(&base64::engine::general_purpose::STANDARD, encoded)
-        .map_err(|e| JsValue::from_str(&format!("Base64 decode error: {}", e)))
-}
\ No newline at end of file
+        .map_err(|e| TransformerError::decode(format!("Base64 decode error: {}", e)).with_field("encoded").into())
+}
+
+/// URL-safe base64 without padding, for use in URLs and query params
+#[wasm_bindgen(js_name = base64UrlEncode)]
+pub fn base64_url_encode(data: &[u8]) -> String {
+    base64::Engine::encode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, data)
+}
+
+#[wasm_bindgen(js_name = base64UrlDecode)]
+pub fn base64_url_decode(encoded: &str) -> Result<Vec<u8>, JsValue> {
+    base64::Engine::decode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, encoded)
+        .map_err(|e| TransformerError::decode(format!("Base64 decode error: {}", e)).with_field("encoded").into())
+}
+
+/// Parse CSV into a JSON array of objects keyed by the header row, or by
+/// col0..colN when `has_header` is false. All values are strings.
+#[wasm_bindgen(js_name = csvToJson)]
+pub fn csv_to_json(csv_str: &str, has_header: bool) -> Result<String, JsValue> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(has_header)
+        .from_reader(csv_str.as_bytes());
+
+    let mut columns: Vec<String> = if has_header {
+        reader.headers()
+            .map_err(|e| TransformerError::parse(format!("Failed to read CSV header: {}", e)).with_field("csv_str"))?
+            .iter()
+            .map(|h| h.to_string())
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let mut rows = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = record
+            .map_err(|e| TransformerError::parse(format!("Malformed CSV at row {}: {}", index, e)).with_field("csv_str"))?;
+
+        if !has_header && columns.len() < record.len() {
+            columns.extend((columns.len()..record.len()).map(|i| format!("col{}", i)));
+        }
+
+        let row: Map<String, Value> = columns.iter()
+            .zip(record.iter())
+            .map(|(column, field)| (column.clone(), Value::String(field.to_string())))
+            .collect();
+        rows.push(Value::Object(row));
+    }
+
+    serde_json::to_string(&rows)
+        .map_err(|e| TransformerError::serialization(format!("Serialization error: {}", e)).into())
+}
+
+/// Write a JSON array of objects as CSV with a header row in the given
+/// column order. Columns accept dot paths; missing values become empty
+/// fields and nested objects/arrays are written as JSON.
+#[wasm_bindgen(js_name = jsonToCsv)]
+pub fn json_to_csv(json_str: &str, columns_json: &str) -> Result<String, JsValue> {
+    let data: Vec<Value> = serde_json::from_str(json_str)
+        .map_err(|e| TransformerError::parse(format!("Failed to parse data: {}", e)).with_field("json_str"))?;
+
+    let columns: Vec<String> = serde_json::from_str(columns_json)
+        .map_err(|e| TransformerError::parse(format!("Failed to parse columns: {}", e)).with_field("columns_json"))?;
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(&columns)
+        .map_err(|e| TransformerError::serialization(format!("CSV write error: {}", e)))?;
+
+    for (index, item) in data.iter().enumerate() {
+        let obj = item.as_object()
+            .ok_or_else(|| TransformerError::validation(format!("Row {} is not an object", index)).with_field("json_str"))?;
+
+        let record: Vec<String> = columns.iter()
+            .map(|column| match get_path(obj, column) {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::String(s)) => s.clone(),
+                Some(other) => other.to_string(),
+            })
+            .collect();
+
+        writer.write_record(&record)
+            .map_err(|e| TransformerError::serialization(format!("CSV write error at row {}: {}", index, e)))?;
+    }
+
+    let bytes = writer.into_inner()
+        .map_err(|e| TransformerError::serialization(format!("CSV write error: {}", e)))?;
+
+    String::from_utf8(bytes)
+        .map_err(|e| TransformerError::serialization(format!("Invalid UTF-8 in CSV output: {}", e)).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::time::Instant;
+
+    fn agg(field: &str, function: AggregateFunctionType) -> AggregateFunction {
+        AggregateFunction { field: field.to_string(), function, alias: field.to_string() }
+    }
+
+    // The per-aggregate passes the single-pass version replaced, kept as a reference
+    fn multi_pass(items: &[&Value], aggregates: &[AggregateFunction]) -> Vec<Value> {
+        aggregates
+            .iter()
+            .map(|agg_func| {
+                let numbers = || items.iter().filter_map(|item| item.get(&agg_func.field).and_then(Value::as_f64));
+                match agg_func.function {
+                    AggregateFunctionType::Sum => Value::from(numbers().sum::<f64>()),
+                    AggregateFunctionType::Average => Value::from(numbers().sum::<f64>() / numbers().count() as f64),
+                    AggregateFunctionType::Min => Value::from(numbers().fold(f64::INFINITY, f64::min)),
+                    AggregateFunctionType::Max => Value::from(numbers().fold(f64::NEG_INFINITY, f64::max)),
+                    AggregateFunctionType::Count => Value::from(items.len()),
+                    AggregateFunctionType::CountDistinct => Value::from(
+                        items.iter()
+                            .filter_map(|item| item.get(&agg_func.field).map(|v| v.to_string()))
+                            .collect::<HashSet<_>>()
+                            .len(),
+                    ),
+                    _ => unreachable!(),
+                }
+            })
+            .collect()
+    }
+
+    fn transformed(transformer: &mut DataTransformer, data: &str, config: &str) -> Value {
+        let output: Value = serde_json::from_str(&transformer.transform_batch(data, config).unwrap()).unwrap();
+        output["data"].clone()
+    }
+
+    #[test]
+    fn transforms_resolve_against_renamed_fields() {
+        let mut transformer = DataTransformer::new();
+        let data = r#"[{"amount": 100.0, "id": 1}]"#;
+        let config = r#"{"field_mappings": {"amount": "price"}, "field_transforms": {"price": {"Multiply": 1.2}}}"#;
+
+        let output = transformed(&mut transformer, data, config);
+        assert_eq!(output[0]["price"], 120.0);
+        assert!(output[0].get("amount").is_none());
+    }
+
+    #[test]
+    fn cache_is_keyed_on_both_inputs() {
+        let mut transformer = DataTransformer::new();
+        let config = r#"{"field_transforms": {"a": "Uppercase"}}"#;
+
+        assert_eq!(transformed(&mut transformer, r#"[{"a": "x"}]"#, config)[0]["a"], "X");
+        assert_eq!(transformed(&mut transformer, r#"[{"a": "y"}]"#, config)[0]["a"], "Y");
+        assert_eq!((transformer.cache_hits, transformer.cache_misses), (0, 2));
+        assert_eq!(transformed(&mut transformer, r#"[{"a": "x"}]"#, config)[0]["a"], "X");
+        assert_eq!((transformer.cache_hits, transformer.cache_misses), (1, 2));
+        assert_eq!((transformer.cache.len(), transformer.cache_order.len()), (2, 2));
+
+        assert_ne!(DataTransformer::cache_key("ab", "c"), DataTransformer::cache_key("a", "bc"));
+    }
+
+    fn pipeline(config: &str, data: &str) -> (Result<Vec<Value>, TransformerError>, Vec<String>) {
+        let config: TransformConfig = serde_json::from_str(config).unwrap();
+        let data: Vec<Value> = serde_json::from_str(data).unwrap();
+        let mut errors = Vec::new();
+        let result = DataTransformer::new().run_pipeline(data.into_iter().enumerate().collect(), &config, "index", &mut errors);
+        (result, errors)
+    }
+
+    #[test]
+    fn non_applicable_transforms_pass_through() {
+        let data = r#"[{"price": "12.5"}, {"price": "POA", "let": "yes", "size": "ten", "d": 5}, {"price": null}]"#;
+        let config = r#"{"field_transforms": {"price": "ToNumber", "let": "ToBool", "size": {"Multiply": 2.0}, "d": {"DateFormat": "%Y"}}}"#;
+        let (result, errors) = pipeline(config, data);
+        assert_eq!(result.unwrap(), vec![
+            serde_json::json!({"price": 12.5}),
+            serde_json::json!({"price": "POA", "let": "yes", "size": "ten", "d": 5}),
+            serde_json::json!({"price": null}),
+        ]);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn strict_mode_aborts_on_transform_errors() {
+        let config = r#"{"field_transforms": {"d": {"DateFormat": "%d/%m/%Y"}}}"#;
+        let data = r#"[{"d": "2024-03-05"}, {"d": "next week"}]"#;
+
+        let (result, errors) = pipeline(config, data);
+        assert_eq!(result.unwrap(), vec![serde_json::json!({"d": "05/03/2024"})]);
+        assert_eq!(errors, vec!["Transform error: d: Cannot parse 'next week' as a date"]);
+
+        let (result, _) = pipeline(&config.replace("}}}", r#"}}, "strict": true}"#), data);
+        let error = result.unwrap_err();
+        assert_eq!(error.code, ErrorCode::TransformError);
+        assert!(error.message.starts_with("Transform error at index 1:"), "{}", error.message);
+    }
+
+    #[test]
+    fn strict_mode_aborts_on_schema_violations() {
+        let schema = r#""schema": {"type": "object", "required": ["id"]}"#;
+        let data = r#"[{"id": 1}, {"name": "no id"}]"#;
+
+        let (result, errors) = pipeline(&format!("{{{}}}", schema), data);
+        assert_eq!(result.unwrap().len(), 1);
+        assert_eq!(errors.len(), 1);
+
+        let (result, errors) = pipeline(&format!(r#"{{{}, "strict": true}}"#, schema), data);
+        let error = result.unwrap_err();
+        assert_eq!(error.code, ErrorCode::ValidationError);
+        assert!(error.message.starts_with("Schema violation at index 1:"), "{}", error.message);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    #[ignore = "benchmark over 1M rows; run with --ignored --release"]
+    fn single_pass_aggregates_beat_multi_pass() {
+        let rows: Vec<Value> = (0..1_000_000)
+            .map(|i| serde_json::json!({ "price": (i % 997) as f64 * 1.5, "postcode": format!("NW{}", i % 50) }))
+            .collect();
+        let mut groups: Vec<Vec<&Value>> = vec![Vec::new(); 1000];
+        for (i, row) in rows.iter().enumerate() {
+            groups[i % 1000].push(row);
+        }
+        let aggregates = vec![
+            agg("price", AggregateFunctionType::Sum),
+            agg("price", AggregateFunctionType::Average),
+            agg("price", AggregateFunctionType::Min),
+            agg("price", AggregateFunctionType::Max),
+            agg("price", AggregateFunctionType::Count),
+            agg("postcode", AggregateFunctionType::CountDistinct),
+        ];
+        let transformer = DataTransformer::new();
+
+        let start = Instant::now();
+        let single: Vec<Vec<Value>> = groups.iter()
+            .map(|items| transformer.calculate_aggregates(items, &aggregates).unwrap())
+            .collect();
+        let single_elapsed = start.elapsed();
+
+        let start = Instant::now();
+        let multi: Vec<Vec<Value>> = groups.iter().map(|items| multi_pass(items, &aggregates)).collect();
+        let multi_elapsed = start.elapsed();
+
+        assert_eq!(single, multi);
+        assert!(
+            single_elapsed < multi_elapsed,
+            "single pass took {:?}, multi pass {:?}",
+            single_elapsed,
+            multi_elapsed
+        );
+    }
+
+    #[test]
+    fn bare_hash_config_keeps_the_legacy_hash() {
+        let mut transformer = DataTransformer::new();
+        let data = r#"[{"a": "owner@example.com", "b": "owner@example.com"}]"#;
+        let config = r#"{"field_transforms": {"a": "Hash", "b": [{"Hash": "Legacy"}]}}"#;
+
+        let output = transformed(&mut transformer, data, config);
+        let expected = "owner@example.com".bytes().fold(0u32, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u32));
+        assert_eq!(output[0]["a"], format!("{:x}", expected));
+        assert_eq!(output[0]["a"], output[0]["b"]);
+    }
+
+    #[test]
+    fn decompress_auto_falls_back_to_lz4_after_a_gzip_lookalike() {
+        let transformer = DataTransformer::new();
+        // 559903 bytes, whose little-endian size prefix is the gzip magic 1f 8b 08 00
+        let mut text: String = (0..).map(|i: u32| format!("{},", i.wrapping_mul(2654435761))).take(60_000).collect();
+        text.truncate(0x0008_8b1f);
+        assert_eq!(text.len(), 0x0008_8b1f);
+
+        let compressed = transformer.compress_lz4(&text).unwrap();
+        assert!(compressed.starts_with(&[0x1f, 0x8b, 0x08, 0x00]));
+        assert_eq!(transformer.decompress_auto(&compressed).unwrap(), text);
+
+        let gzipped = transformer.compress(&text).unwrap();
+        assert_eq!(transformer.decompress_auto(&gzipped).unwrap(), text);
+    }
+}
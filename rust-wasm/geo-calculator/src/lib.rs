@@ -1,13 +1,18 @@
 use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
 use geo::{
-    algorithm::{area::Area, centroid::Centroid, contains::Contains, haversine_distance::HaversineDistance},
-    Point, Polygon, LineString, Coord,
+    algorithm::{
+        bool_ops::BooleanOps, bounding_rect::BoundingRect, centroid::Centroid,
+        contains::Contains, geodesic_area::GeodesicArea, haversine_distance::HaversineDistance,
+        line_intersection::{line_intersection, LineIntersection}, orient::{Direction, Orient},
+    },
+    Point, Polygon, Line, LineString, Coord, Rect,
 };
 use geo_types::{Geometry, MultiPolygon};
-use rstar::{RTree, AABB};
+use rstar::{primitives::GeomWithData, RTree, SelectionFunction, AABB};
 use ahash::{AHashMap, AHashSet};
-use std::f64::consts::PI;
+use std::cell::RefCell;
+use std::f64::consts::{FRAC_PI_4, PI};
 
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
@@ -38,6 +43,15 @@ pub struct GeoPolygon {
     pub properties: Option<AHashMap<String, String>>,
 }
 
+/// A single polygon, or an array of parts for areas with detached pieces
+/// (islands, exclaves)
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PolygonInput {
+    Single(GeoPolygon),
+    Multi(Vec<GeoPolygon>),
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ProximityResult {
     pub id: String,
@@ -46,6 +60,14 @@ pub struct ProximityResult {
     pub location: LatLng,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EllipsoidalDistance {
+    pub distance_meters: f64,
+    /// True when Vincenty didn't converge (near-antipodal points) and the
+    /// distance is a Haversine estimate instead
+    pub fallback: bool,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ClusterResult {
     pub center: LatLng,
@@ -54,6 +76,281 @@ pub struct ClusterResult {
     pub items: Vec<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IntersectionKind {
+    None,
+    /// The segments cross at a point interior to both
+    Crossing,
+    /// An endpoint of one segment lies on the other
+    Touching,
+    /// The segments meet at an endpoint they both share
+    SharedEndpoint,
+    /// The segments are collinear and overlap along a stretch
+    CollinearOverlap,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SegmentIntersection {
+    pub intersects: bool,
+    pub kind: IntersectionKind,
+    /// Intersection point for crossing, touching and shared-endpoint cases
+    pub point: Option<LatLng>,
+    /// Ends of the shared stretch for a collinear overlap
+    pub overlap: Option<[LatLng; 2]>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConvexHull {
+    pub polygon: GeoPolygon,
+    /// True when there were fewer than three distinct, non-collinear points;
+    /// the polygon then holds just the distinct points
+    pub degenerate: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DbscanResult {
+    pub clusters: Vec<ClusterResult>,
+    pub noise: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct KMeansCluster {
+    /// None for a cluster left empty because k exceeded the point count
+    pub centroid: Option<LatLng>,
+    pub count: usize,
+    pub items: Vec<String>,
+}
+
+// SplitMix64, so k-means seeding is reproducible without an RNG dependency
+struct SeededRng(u64);
+
+impl SeededRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[derive(Deserialize)]
+struct PointData {
+    id: String,
+    lat: f64,
+    lng: f64,
+}
+
+/// Geographic centre and bounding box of a group of points
+fn summarize_cluster(items: Vec<String>, lats: &[f64], lngs: &[f64]) -> ClusterResult {
+    // Calculate cluster center, falling back to the plain mean when the
+    // points are spread so evenly that the centre is undefined
+    let center = unit_vector_center(lats.iter().copied().zip(lngs.iter().copied()))
+        .unwrap_or_else(|| LatLng {
+            lat: lats.iter().sum::<f64>() / lats.len() as f64,
+            lng: lngs.iter().sum::<f64>() / lngs.len() as f64,
+        });
+
+    // Calculate bounding box
+    let min_lat = lats.iter().fold(f64::INFINITY, |a, &b| a.min(b));
+    let max_lat = lats.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
+    let min_lng = lngs.iter().fold(f64::INFINITY, |a, &b| a.min(b));
+    let max_lng = lngs.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
+
+    ClusterResult {
+        center,
+        count: items.len(),
+        bbox: BoundingBox {
+            min_lat,
+            min_lng,
+            max_lat,
+            max_lng,
+        },
+        items,
+    }
+}
+
+/// Centre of mass of points on the unit sphere: the normalised sum of their
+/// Cartesian unit vectors, which unlike averaging lat/lng stays correct
+/// across the antimeridian and near the poles. None when there are no
+/// points or the vectors cancel out.
+fn unit_vector_center(points: impl Iterator<Item = (f64, f64)>) -> Option<LatLng> {
+    let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+    for (lat, lng) in points {
+        let (lat, lng) = (lat.to_radians(), lng.to_radians());
+        x += lat.cos() * lng.cos();
+        y += lat.cos() * lng.sin();
+        z += lat.sin();
+    }
+
+    let horizontal = x.hypot(y);
+    if horizontal.hypot(z) < 1e-12 {
+        return None;
+    }
+
+    Some(LatLng {
+        lat: z.atan2(horizontal).to_degrees(),
+        lng: y.atan2(x).to_degrees(),
+    })
+}
+
+/// Forward spherical geodesic: the point `distance_meters` from the start
+/// along the initial bearing
+fn destination(lat: f64, lng: f64, bearing_degrees: f64, distance_meters: f64) -> LatLng {
+    let angular_distance = distance_meters / EARTH_RADIUS_M;
+    let bearing = bearing_degrees.to_radians();
+    let lat1 = lat.to_radians();
+    let lng1 = lng.to_radians();
+
+    let lat2 = (lat1.sin() * angular_distance.cos()
+        + lat1.cos() * angular_distance.sin() * bearing.cos())
+        .clamp(-1.0, 1.0)
+        .asin();
+    let lng2 = lng1 + (bearing.sin() * angular_distance.sin() * lat1.cos())
+        .atan2(angular_distance.cos() - lat1.sin() * lat2.sin());
+
+    // Normalize longitude to -180..180
+    let lng_deg = (lng2.to_degrees() + 540.0).rem_euclid(360.0) - 180.0;
+    LatLng { lat: lat2.to_degrees(), lng: lng_deg }
+}
+
+/// Longitude difference in radians, taking the shorter way across the
+/// antimeridian
+fn rhumb_longitude_delta(lng1: f64, lng2: f64) -> f64 {
+    let mut dlon = (lng2 - lng1).to_radians();
+    if dlon.abs() > PI {
+        dlon -= dlon.signum() * 2.0 * PI;
+    }
+    dlon
+}
+
+/// Difference in Mercator-projected latitude between two latitudes (radians)
+fn mercator_latitude_delta(lat1: f64, lat2: f64) -> f64 {
+    ((lat2 / 2.0 + FRAC_PI_4).tan() / (lat1 / 2.0 + FRAC_PI_4).tan()).ln()
+}
+
+/// GeoJSON geometry for a MultiPolygon: a Polygon when there is exactly
+/// one part, otherwise a (possibly empty) MultiPolygon
+fn to_geojson(multi_polygon: &MultiPolygon<f64>) -> serde_json::Value {
+    let ring = |line: &LineString<f64>| -> Vec<[f64; 2]> {
+        line.coords().map(|c| [c.x, c.y]).collect()
+    };
+    let rings = |polygon: &Polygon<f64>| -> Vec<Vec<[f64; 2]>> {
+        std::iter::once(polygon.exterior())
+            .chain(polygon.interiors())
+            .map(ring)
+            .collect()
+    };
+
+    match multi_polygon.0.as_slice() {
+        [polygon] => serde_json::json!({ "type": "Polygon", "coordinates": rings(polygon) }),
+        parts => serde_json::json!({
+            "type": "MultiPolygon",
+            "coordinates": parts.iter().map(rings).collect::<Vec<_>>(),
+        }),
+    }
+}
+
+fn segment_lengths(points: &[LatLng]) -> impl Iterator<Item = f64> + '_ {
+    points.windows(2)
+        .map(|pair| haversine(pair[0].lat, pair[0].lng, pair[1].lat, pair[1].lng))
+}
+
+fn parse_polyline(points_json: &str) -> Result<Vec<LatLng>, JsValue> {
+    serde_json::from_str(points_json)
+        .map_err(|e| JsValue::from_str(&format!("Failed to parse points: {}", e)))
+}
+
+/// Distances memoized before the cache is reset, bounding its memory
+const MAX_CACHED_DISTANCES: usize = 10_000;
+
+/// Coordinates in millionths of a degree, used to key memoized distances
+type DistanceKey = (i64, i64, i64, i64);
+
+fn distance_key(lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> DistanceKey {
+    let micro = |degrees: f64| (degrees * 1e6).round() as i64;
+    (micro(lat1), micro(lng1), micro(lat2), micro(lng2))
+}
+
+fn haversine(lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> f64 {
+    Point::new(lng1, lat1).haversine_distance(&Point::new(lng2, lat2))
+}
+
+const DEFAULT_MAX_MATRIX_CELLS: usize = 1_000_000;
+
+// Mean Earth radius, matching geo's Haversine implementation
+const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+/// Latitude and longitude spans, in degrees, that enclose a circle of
+/// `meters` around a point at `lat`
+fn degree_deltas(lat: f64, meters: f64) -> (f64, f64) {
+    let lat_delta = (meters / EARTH_RADIUS_M).to_degrees();
+    let cos_lat = lat.to_radians().cos();
+    let lng_delta = if cos_lat > 1e-6 { (lat_delta / cos_lat).min(360.0) } else { 360.0 };
+    (lat_delta, lng_delta)
+}
+
+// WGS-84 ellipsoid
+const WGS84_A: f64 = 6_378_137.0;
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+const VINCENTY_MAX_ITERATIONS: usize = 200;
+
+/// Inverse Vincenty distance on the WGS-84 ellipsoid, or None if the
+/// iteration fails to converge
+fn vincenty_distance(lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> Option<f64> {
+    let b = (1.0 - WGS84_F) * WGS84_A;
+    let l = (lng2 - lng1).to_radians();
+    let u1 = ((1.0 - WGS84_F) * lat1.to_radians().tan()).atan();
+    let u2 = ((1.0 - WGS84_F) * lat2.to_radians().tan()).atan();
+    let (sin_u1, cos_u1) = u1.sin_cos();
+    let (sin_u2, cos_u2) = u2.sin_cos();
+
+    let mut lambda = l;
+    for _ in 0..VINCENTY_MAX_ITERATIONS {
+        let (sin_lambda, cos_lambda) = lambda.sin_cos();
+        let sin_sigma = ((cos_u2 * sin_lambda).powi(2)
+            + (cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda).powi(2))
+            .sqrt();
+        if sin_sigma == 0.0 {
+            // Coincident points
+            return Some(0.0);
+        }
+
+        let cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_lambda;
+        let sigma = sin_sigma.atan2(cos_sigma);
+        let sin_alpha = cos_u1 * cos_u2 * sin_lambda / sin_sigma;
+        let cos_sq_alpha = 1.0 - sin_alpha * sin_alpha;
+        // Both points on the equator
+        let cos_2sigma_m = if cos_sq_alpha == 0.0 { 0.0 } else { cos_sigma - 2.0 * sin_u1 * sin_u2 / cos_sq_alpha };
+        let c = WGS84_F / 16.0 * cos_sq_alpha * (4.0 + WGS84_F * (4.0 - 3.0 * cos_sq_alpha));
+
+        let previous = lambda;
+        lambda = l + (1.0 - c) * WGS84_F * sin_alpha
+            * (sigma + c * sin_sigma * (cos_2sigma_m + c * cos_sigma * (-1.0 + 2.0 * cos_2sigma_m * cos_2sigma_m)));
+
+        if (lambda - previous).abs() < 1e-12 {
+            let u_sq = cos_sq_alpha * (WGS84_A * WGS84_A - b * b) / (b * b);
+            let big_a = 1.0 + u_sq / 16384.0 * (4096.0 + u_sq * (-768.0 + u_sq * (320.0 - 175.0 * u_sq)));
+            let big_b = u_sq / 1024.0 * (256.0 + u_sq * (-128.0 + u_sq * (74.0 - 47.0 * u_sq)));
+            let delta_sigma = big_b * sin_sigma
+                * (cos_2sigma_m + big_b / 4.0
+                    * (cos_sigma * (-1.0 + 2.0 * cos_2sigma_m * cos_2sigma_m)
+                        - big_b / 6.0 * cos_2sigma_m * (-3.0 + 4.0 * sin_sigma * sin_sigma)
+                            * (-3.0 + 4.0 * cos_2sigma_m * cos_2sigma_m)));
+
+            return Some(b * big_a * (sigma - delta_sigma));
+        }
+    }
+
+    None
+}
+
 // Spatial index for fast proximity queries
 struct SpatialLocation {
     id: String,
@@ -68,11 +365,41 @@ impl rstar::RTreeObject for SpatialLocation {
     }
 }
 
+// Locations carry no position index by id, so removal visits every node
+struct SelectById<'a>(&'a str);
+
+impl SelectionFunction<SpatialLocation> for SelectById<'_> {
+    fn should_unpack_parent(&self, _envelope: &AABB<Point<f64>>) -> bool {
+        true
+    }
+
+    fn should_unpack_leaf(&self, leaf: &SpatialLocation) -> bool {
+        leaf.id == self.0
+    }
+}
+
+// Loaded polygon with its bounding box, used to skip the exact test for
+// points clearly outside
+struct LoadedPolygon {
+    shape: MultiPolygon<f64>,
+    bounds: Rect<f64>,
+}
+
+impl LoadedPolygon {
+    fn contains(&self, point: &Point<f64>) -> bool {
+        let (min, max) = (self.bounds.min(), self.bounds.max());
+        if point.x() < min.x || point.x() > max.x || point.y() < min.y || point.y() > max.y {
+            return false;
+        }
+        self.shape.iter().any(|polygon| polygon.contains(point))
+    }
+}
+
 #[wasm_bindgen]
 pub struct GeoCalculator {
     rtree: Option<RTree<SpatialLocation>>,
-    polygons: AHashMap<String, Polygon<f64>>,
-    cached_distances: AHashMap<String, f64>,
+    polygons: AHashMap<String, LoadedPolygon>,
+    cached_distances: RefCell<AHashMap<DistanceKey, f64>>,
 }
 
 #[wasm_bindgen]
@@ -83,16 +410,51 @@ impl GeoCalculator {
         GeoCalculator {
             rtree: None,
             polygons: AHashMap::new(),
-            cached_distances: AHashMap::new(),
+            cached_distances: RefCell::new(AHashMap::new()),
         }
     }
 
-    /// Calculate distance between two points using Haversine formula
+    /// Calculate distance between two points using Haversine formula.
+    /// Results are memoized by coordinates rounded to 6 decimal places, so
+    /// points within about 0.1m of a cached pair reuse its distance; see
+    /// clearDistanceCache.
     #[wasm_bindgen(js_name = calculateDistance)]
     pub fn calculate_distance(&self, lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> f64 {
-        let point1 = Point::new(lng1, lat1);
-        let point2 = Point::new(lng2, lat2);
-        point1.haversine_distance(&point2)
+        let key = distance_key(lat1, lng1, lat2, lng2);
+        if let Some(&distance) = self.cached_distances.borrow().get(&key) {
+            return distance;
+        }
+
+        let distance = haversine(lat1, lng1, lat2, lng2);
+        let mut cache = self.cached_distances.borrow_mut();
+        if cache.len() >= MAX_CACHED_DISTANCES {
+            cache.clear();
+        }
+        cache.insert(key, distance);
+        distance
+    }
+
+    /// Drop memoized distances, keeping the spatial index and polygons
+    #[wasm_bindgen(js_name = clearDistanceCache)]
+    pub fn clear_distance_cache(&mut self) {
+        self.cached_distances.borrow_mut().clear();
+    }
+
+    /// Distance on the WGS-84 ellipsoid using Vincenty's inverse formula.
+    /// Slower but more accurate than calculateDistance; near-antipodal
+    /// points fall back to Haversine and are flagged.
+    #[wasm_bindgen(js_name = calculateDistanceVincenty)]
+    pub fn calculate_distance_vincenty(&self, lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> Result<String, JsValue> {
+        let result = match vincenty_distance(lat1, lng1, lat2, lng2) {
+            Some(distance_meters) => EllipsoidalDistance { distance_meters, fallback: false },
+            None => EllipsoidalDistance {
+                distance_meters: haversine(lat1, lng1, lat2, lng2),
+                fallback: true,
+            },
+        };
+
+        serde_json::to_string(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
 
     /// Calculate bearing between two points
@@ -112,53 +474,250 @@ impl GeoCalculator {
         (bearing_deg + 360.0) % 360.0
     }
 
-    /// Calculate area of a polygon in square meters
-    #[wasm_bindgen(js_name = calculateArea)]
-    pub fn calculate_area(&self, polygon_json: &str) -> Result<f64, JsValue> {
-        let geo_polygon: GeoPolygon = serde_json::from_str(polygon_json)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse polygon: {}", e)))?;
+    /// Rhumb-line (constant bearing) distance in meters on a spherical Earth
+    #[wasm_bindgen(js_name = rhumbDistance)]
+    pub fn rhumb_distance(&self, lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> f64 {
+        let lat1_rad = lat1.to_radians();
+        let lat2_rad = lat2.to_radians();
+        let dlat = lat2_rad - lat1_rad;
+        let dlon = rhumb_longitude_delta(lng1, lng2);
+        let dpsi = mercator_latitude_delta(lat1_rad, lat2_rad);
+
+        // On an east-west line the projected ratio is 0/0, so use cos(lat)
+        let q = if dpsi.abs() > 1e-12 { dlat / dpsi } else { lat1_rad.cos() };
+
+        (dlat * dlat + q * q * dlon * dlon).sqrt() * EARTH_RADIUS_M
+    }
+
+    /// Constant bearing in degrees (0-360) of the rhumb line between two points
+    #[wasm_bindgen(js_name = rhumbBearing)]
+    pub fn rhumb_bearing(&self, lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> f64 {
+        let dlon = rhumb_longitude_delta(lng1, lng2);
+        let dpsi = mercator_latitude_delta(lat1.to_radians(), lat2.to_radians());
+
+        let bearing_deg = dlon.atan2(dpsi).to_degrees();
+
+        // Normalize to 0-360
+        (bearing_deg + 360.0) % 360.0
+    }
+
+    /// Point reached by travelling `distance_meters` from a start point on
+    /// the given initial bearing, on a spherical Earth (the inverse of
+    /// calculateBearing/calculateDistance)
+    #[wasm_bindgen(js_name = destinationPoint)]
+    pub fn destination_point(&self, lat: f64, lng: f64, bearing_degrees: f64, distance_meters: f64) -> Result<String, JsValue> {
+        let destination = destination(lat, lng, bearing_degrees, distance_meters);
+
+        serde_json::to_string(&destination)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Haversine distances in meters from every origin (rows) to every
+    /// destination (columns). Errors if the matrix would exceed `max_cells`
+    /// (default 1,000,000).
+    #[wasm_bindgen(js_name = distanceMatrix)]
+    pub fn distance_matrix(&self, origins_json: &str, destinations_json: &str, max_cells: Option<usize>) -> Result<String, JsValue> {
+        let origins: Vec<LatLng> = serde_json::from_str(origins_json)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse origins: {}", e)))?;
+        let destinations: Vec<LatLng> = serde_json::from_str(destinations_json)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse destinations: {}", e)))?;
+
+        let limit = max_cells.unwrap_or(DEFAULT_MAX_MATRIX_CELLS);
+        let cells = origins.len().saturating_mul(destinations.len());
+        if cells > limit {
+            return Err(JsValue::from_str(&format!(
+                "Distance matrix of {} x {} exceeds the limit of {} cells",
+                origins.len(), destinations.len(), limit
+            )));
+        }
+
+        let matrix: Vec<Vec<f64>> = origins.iter()
+            .map(|o| {
+                destinations.iter()
+                    .map(|d| haversine(o.lat, o.lng, d.lat, d.lng))
+                    .collect()
+            })
+            .collect();
+
+        serde_json::to_string(&matrix)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Midpoint of the great-circle path between two points
+    #[wasm_bindgen(js_name = midpoint)]
+    pub fn midpoint(&self, lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> Result<String, JsValue> {
+        let center = unit_vector_center([(lat1, lng1), (lat2, lng2)].into_iter())
+            .ok_or_else(|| JsValue::from_str("Midpoint of antipodal points is undefined"))?;
+
+        serde_json::to_string(&center)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Geographic centre of a set of points, averaging Cartesian unit
+    /// vectors rather than latitudes and longitudes
+    #[wasm_bindgen(js_name = geographicCenter)]
+    pub fn geographic_center(&self, points_json: &str) -> Result<String, JsValue> {
+        let points: Vec<LatLng> = serde_json::from_str(points_json)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse points: {}", e)))?;
+
+        let center = unit_vector_center(points.iter().map(|p| (p.lat, p.lng)))
+            .ok_or_else(|| JsValue::from_str("Points have no defined geographic center"))?;
+
+        serde_json::to_string(&center)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
 
-        let polygon = self.create_polygon(&geo_polygon)?;
+    /// Polygon approximating a circle of `radius_meters`, with one vertex
+    /// per segment at evenly spaced bearings from the centre
+    #[wasm_bindgen(js_name = circlePolygon)]
+    pub fn circle_polygon(&self, lat: f64, lng: f64, radius_meters: f64, segments: usize) -> Result<String, JsValue> {
+        if segments < 3 {
+            return Err(JsValue::from_str(&format!("segments must be at least 3, got {}", segments)));
+        }
+        if !radius_meters.is_finite() || radius_meters <= 0.0 {
+            return Err(JsValue::from_str(&format!("radius_meters must be positive, got {}", radius_meters)));
+        }
 
-        // Convert area from square degrees to square meters
-        // This is an approximation that works well for small areas
-        let area_degrees = polygon.unsigned_area();
-        let lat_center = geo_polygon.coordinates.iter()
-            .map(|c| c.lat)
-            .sum::<f64>() / geo_polygon.coordinates.len() as f64;
+        let coordinates = (0..segments)
+            .map(|i| destination(lat, lng, 360.0 * i as f64 / segments as f64, radius_meters))
+            .collect();
+        let polygon = GeoPolygon {
+            coordinates,
+            holes: None,
+            properties: None,
+        };
 
-        // Earth radius in meters
-        const EARTH_RADIUS_M: f64 = 6_371_000.0;
+        serde_json::to_string(&polygon)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
 
-        // Convert to square meters using latitude-adjusted scale
-        let lat_rad = lat_center.to_radians();
-        let meters_per_degree_lat = EARTH_RADIUS_M * PI / 180.0;
-        let meters_per_degree_lng = meters_per_degree_lat * lat_rad.cos();
+    /// Total length in meters of a route through the given points, summing
+    /// Haversine segments (a great-circle approximation). Fewer than two
+    /// points give 0.
+    #[wasm_bindgen(js_name = polylineLength)]
+    pub fn polyline_length(&self, points_json: &str) -> Result<f64, JsValue> {
+        let points = parse_polyline(points_json)?;
 
-        Ok(area_degrees * meters_per_degree_lat * meters_per_degree_lng)
+        Ok(segment_lengths(&points).sum())
     }
 
-    /// Check if a point is inside a polygon
+    /// Running great-circle distance in meters at each vertex of a route,
+    /// starting at 0 for the first point
+    #[wasm_bindgen(js_name = cumulativePolylineDistance)]
+    pub fn cumulative_polyline_distance(&self, points_json: &str) -> Result<String, JsValue> {
+        let points = parse_polyline(points_json)?;
+
+        let mut running = 0.0;
+        let mut distances = Vec::with_capacity(points.len());
+        if !points.is_empty() {
+            distances.push(0.0);
+        }
+        for length in segment_lengths(&points) {
+            running += length;
+            distances.push(running);
+        }
+
+        serde_json::to_string(&distances)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Calculate area of a polygon, or the summed area of a multi-part
+    /// polygon, in square meters
+    #[wasm_bindgen(js_name = calculateArea)]
+    pub fn calculate_area(&self, polygon_json: &str) -> Result<f64, JsValue> {
+        let multi_polygon = self.parse_polygon(polygon_json)?;
+
+        // Geodesic area on the WGS-84 ellipsoid, with holes subtracted. The
+        // rings are oriented first (exterior counter-clockwise, holes
+        // clockwise) as the geodesic computation assumes that winding.
+        Ok(multi_polygon.iter()
+            .map(|polygon| polygon.orient(Direction::Default).geodesic_area_unsigned())
+            .sum())
+    }
+
+    /// Check if a point is inside a polygon (inside any part of a
+    /// multi-part polygon)
     #[wasm_bindgen(js_name = isPointInPolygon)]
     pub fn is_point_in_polygon(&self, lat: f64, lng: f64, polygon_id: &str) -> bool {
         if let Some(polygon) = self.polygons.get(polygon_id) {
-            let point = Point::new(lng, lat);
-            polygon.contains(&point)
+            polygon.contains(&Point::new(lng, lat))
         } else {
             false
         }
     }
 
-    /// Load a polygon for spatial queries
+    /// Ids of the points inside a loaded polygon, checking each point
+    /// against the polygon's bounding box before the exact test
+    #[wasm_bindgen(js_name = pointsInPolygon)]
+    pub fn points_in_polygon(&self, points_json: &str, polygon_id: &str) -> Result<String, JsValue> {
+        let polygon = self.loaded_polygon(polygon_id)?;
+
+        let points: Vec<PointData> = serde_json::from_str(points_json)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse points: {}", e)))?;
+
+        let inside: Vec<String> = points
+            .into_iter()
+            .filter(|p| polygon.contains(&Point::new(p.lng, p.lat)))
+            .map(|p| p.id)
+            .collect();
+
+        serde_json::to_string(&inside)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Overlap of two loaded polygons as a GeoJSON geometry; an empty
+    /// MultiPolygon when they don't overlap
+    #[wasm_bindgen(js_name = polygonIntersection)]
+    pub fn polygon_intersection(&self, id1: &str, id2: &str) -> Result<String, JsValue> {
+        let (a, b) = (self.loaded_polygon(id1)?, self.loaded_polygon(id2)?);
+
+        serde_json::to_string(&to_geojson(&a.shape.intersection(&b.shape)))
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Combined extent of two loaded polygons as a GeoJSON geometry
+    #[wasm_bindgen(js_name = polygonUnion)]
+    pub fn polygon_union(&self, id1: &str, id2: &str) -> Result<String, JsValue> {
+        let (a, b) = (self.loaded_polygon(id1)?, self.loaded_polygon(id2)?);
+
+        serde_json::to_string(&to_geojson(&a.shape.union(&b.shape)))
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    fn loaded_polygon(&self, polygon_id: &str) -> Result<&LoadedPolygon, JsValue> {
+        self.polygons.get(polygon_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Polygon '{}' is not loaded", polygon_id)))
+    }
+
+    /// Load a polygon, or an array of polygon parts, for spatial queries
     #[wasm_bindgen(js_name = loadPolygon)]
     pub fn load_polygon(&mut self, id: &str, polygon_json: &str) -> Result<(), JsValue> {
-        let geo_polygon: GeoPolygon = serde_json::from_str(polygon_json)
+        let shape = self.parse_polygon(polygon_json)?;
+        let bounds = shape.bounding_rect()
+            .ok_or_else(|| JsValue::from_str("Polygon has no coordinates"))?;
+        self.polygons.insert(id.to_string(), LoadedPolygon { shape, bounds });
+
+        Ok(())
+    }
+
+    /// Parse a single polygon or array of parts into a MultiPolygon
+    fn parse_polygon(&self, polygon_json: &str) -> Result<MultiPolygon<f64>, JsValue> {
+        let input: PolygonInput = serde_json::from_str(polygon_json)
             .map_err(|e| JsValue::from_str(&format!("Failed to parse polygon: {}", e)))?;
 
-        let polygon = self.create_polygon(&geo_polygon)?;
-        self.polygons.insert(id.to_string(), polygon);
+        let parts = match input {
+            PolygonInput::Single(geo_polygon) => vec![self.create_polygon(&geo_polygon)?],
+            PolygonInput::Multi(geo_polygons) => {
+                if geo_polygons.is_empty() {
+                    return Err(JsValue::from_str("Multi-part polygon must have at least one part"));
+                }
+                geo_polygons.iter()
+                    .map(|geo_polygon| self.create_polygon(geo_polygon))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+        };
 
-        Ok(())
+        Ok(MultiPolygon::new(parts))
     }
 
     /// Create a polygon from GeoPolygon
@@ -229,6 +788,28 @@ impl GeoCalculator {
         Ok(count)
     }
 
+    /// Add a single location to the spatial index, creating an empty index
+    /// if none has been built yet
+    #[wasm_bindgen(js_name = insertLocation)]
+    pub fn insert_location(&mut self, id: &str, lat: f64, lng: f64) {
+        self.rtree
+            .get_or_insert_with(RTree::new)
+            .insert(SpatialLocation {
+                id: id.to_string(),
+                point: Point::new(lng, lat),
+            });
+    }
+
+    /// Remove every location with the given id from the spatial index,
+    /// returning whether anything was removed
+    #[wasm_bindgen(js_name = removeLocation)]
+    pub fn remove_location(&mut self, id: &str) -> bool {
+        match self.rtree.as_mut() {
+            Some(rtree) => rtree.drain_with_selection_function(SelectById(id)).count() > 0,
+            None => false,
+        }
+    }
+
     /// Find nearest neighbors using spatial index
     #[wasm_bindgen(js_name = findNearest)]
     pub fn find_nearest(&self, lat: f64, lng: f64, max_results: usize) -> Result<String, JsValue> {
@@ -241,7 +822,7 @@ impl GeoCalculator {
 
         let mut results = Vec::new();
         for location in nearest {
-            let distance = self.calculate_distance(lat, lng, location.point.y(), location.point.x());
+            let distance = haversine(lat, lng, location.point.y(), location.point.x());
             let bearing = self.calculate_bearing(lat, lng, location.point.y(), location.point.x());
 
             results.push(ProximityResult {
@@ -259,27 +840,26 @@ impl GeoCalculator {
             .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
 
-    /// Find all points within radius
+    /// Find all points within radius, nearest first, optionally limited to
+    /// the nearest `max_results`
     #[wasm_bindgen(js_name = findWithinRadius)]
-    pub fn find_within_radius(&self, lat: f64, lng: f64, radius_meters: f64) -> Result<String, JsValue> {
+    pub fn find_within_radius(&self, lat: f64, lng: f64, radius_meters: f64, max_results: Option<usize>) -> Result<String, JsValue> {
         let rtree = self.rtree.as_ref()
             .ok_or_else(|| JsValue::from_str("Spatial index not built"))?;
 
-        // Convert radius to approximate degrees (rough estimation)
-        const METERS_PER_DEGREE: f64 = 111_320.0;
-        let radius_degrees = radius_meters / METERS_PER_DEGREE;
+        // Degrees of longitude shrink with cos(lat), so widen the box east-west
+        let (lat_delta, lng_delta) = degree_deltas(lat, radius_meters);
 
-        let query_point = Point::new(lng, lat);
         let search_bounds = AABB::from_corners(
-            Point::new(lng - radius_degrees, lat - radius_degrees),
-            Point::new(lng + radius_degrees, lat + radius_degrees),
+            Point::new(lng - lng_delta, lat - lat_delta),
+            Point::new(lng + lng_delta, lat + lat_delta),
         );
 
         let candidates: Vec<_> = rtree.locate_in_envelope(&search_bounds).collect();
 
         let mut results = Vec::new();
         for location in candidates {
-            let distance = self.calculate_distance(lat, lng, location.point.y(), location.point.x());
+            let distance = haversine(lat, lng, location.point.y(), location.point.x());
 
             if distance <= radius_meters {
                 let bearing = self.calculate_bearing(lat, lng, location.point.y(), location.point.x());
@@ -296,8 +876,11 @@ impl GeoCalculator {
             }
         }
 
-        // Sort by distance
+        // Sort by distance, keeping only the nearest `max_results` if given
         results.sort_by(|a, b| a.distance_meters.partial_cmp(&b.distance_meters).unwrap());
+        if let Some(limit) = max_results {
+            results.truncate(limit);
+        }
 
         serde_json::to_string(&results)
             .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
@@ -339,13 +922,6 @@ impl GeoCalculator {
     /// Cluster points by proximity
     #[wasm_bindgen(js_name = clusterPoints)]
     pub fn cluster_points(&self, points_json: &str, cluster_radius_meters: f64) -> Result<String, JsValue> {
-        #[derive(Deserialize)]
-        struct PointData {
-            id: String,
-            lat: f64,
-            lng: f64,
-        }
-
         let points: Vec<PointData> = serde_json::from_str(points_json)
             .map_err(|e| JsValue::from_str(&format!("Failed to parse points: {}", e)))?;
 
@@ -369,7 +945,7 @@ impl GeoCalculator {
                     continue;
                 }
 
-                let distance = self.calculate_distance(point.lat, point.lng, other.lat, other.lng);
+                let distance = haversine(point.lat, point.lng, other.lat, other.lng);
                 if distance <= cluster_radius_meters {
                     cluster_items.push(other.id.clone());
                     cluster_lats.push(other.lat);
@@ -378,33 +954,333 @@ impl GeoCalculator {
                 }
             }
 
-            // Calculate cluster center
-            let center_lat = cluster_lats.iter().sum::<f64>() / cluster_lats.len() as f64;
-            let center_lng = cluster_lngs.iter().sum::<f64>() / cluster_lngs.len() as f64;
-
-            // Calculate bounding box
-            let min_lat = cluster_lats.iter().fold(f64::INFINITY, |a, &b| a.min(b));
-            let max_lat = cluster_lats.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-            let min_lng = cluster_lngs.iter().fold(f64::INFINITY, |a, &b| a.min(b));
-            let max_lng = cluster_lngs.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-
-            clusters.push(ClusterResult {
-                center: LatLng { lat: center_lat, lng: center_lng },
-                count: cluster_items.len(),
-                bbox: BoundingBox {
-                    min_lat,
-                    min_lng,
-                    max_lat,
-                    max_lng,
-                },
-                items: cluster_items,
-            });
+            clusters.push(summarize_cluster(cluster_items, &cluster_lats, &cluster_lngs));
         }
 
         serde_json::to_string(&clusters)
             .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
 
+    /// Convex hull of a point set, as a counter-clockwise GeoPolygon, using
+    /// Andrew's monotone chain on equirectangular-projected coordinates
+    #[wasm_bindgen(js_name = convexHull)]
+    pub fn convex_hull(&self, points_json: &str) -> Result<String, JsValue> {
+        let points: Vec<LatLng> = serde_json::from_str(points_json)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse points: {}", e)))?;
+
+        let mut unique: Vec<LatLng> = Vec::with_capacity(points.len());
+        for p in points {
+            if !unique.iter().any(|u| u.lat == p.lat && u.lng == p.lng) {
+                unique.push(p);
+            }
+        }
+
+        let mean_lat = unique.iter().map(|p| p.lat).sum::<f64>() / unique.len().max(1) as f64;
+        let scale = mean_lat.to_radians().cos();
+        let project = |p: &LatLng| (p.lng * scale, p.lat);
+
+        unique.sort_by(|a, b| {
+            let (ax, ay) = project(a);
+            let (bx, by) = project(b);
+            ax.total_cmp(&bx).then(ay.total_cmp(&by))
+        });
+
+        // Cross product of (a - o) and (b - o); positive for a left turn
+        let cross = |o: &LatLng, a: &LatLng, b: &LatLng| {
+            let (ox, oy) = project(o);
+            let (ax, ay) = project(a);
+            let (bx, by) = project(b);
+            (ax - ox) * (by - oy) - (ay - oy) * (bx - ox)
+        };
+
+        let mut hull: Vec<LatLng> = Vec::with_capacity(unique.len() + 1);
+        if unique.len() >= 3 {
+            // Lower hull
+            for p in &unique {
+                while hull.len() >= 2 && cross(&hull[hull.len() - 2], &hull[hull.len() - 1], p) <= 0.0 {
+                    hull.pop();
+                }
+                hull.push(*p);
+            }
+            // Upper hull, never popping back into the lower one
+            let lower_len = hull.len() + 1;
+            for p in unique.iter().rev().skip(1) {
+                while hull.len() >= lower_len && cross(&hull[hull.len() - 2], &hull[hull.len() - 1], p) <= 0.0 {
+                    hull.pop();
+                }
+                hull.push(*p);
+            }
+            // The last point repeats the first
+            hull.pop();
+        }
+
+        let result = if hull.len() >= 3 {
+            ConvexHull {
+                polygon: GeoPolygon { coordinates: hull, holes: None, properties: None },
+                degenerate: false,
+            }
+        } else {
+            ConvexHull {
+                polygon: GeoPolygon { coordinates: unique, holes: None, properties: None },
+                degenerate: true,
+            }
+        };
+
+        serde_json::to_string(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Density-based clustering: a point with at least `min_points`
+    /// neighbours (itself included) within `eps_meters` seeds a cluster,
+    /// which grows through every such core point it reaches. Points in no
+    /// cluster are returned as noise. Neighbours come from an R-tree.
+    #[wasm_bindgen(js_name = dbscan)]
+    pub fn dbscan(&self, points_json: &str, eps_meters: f64, min_points: usize) -> Result<String, JsValue> {
+        let points: Vec<PointData> = serde_json::from_str(points_json)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse points: {}", e)))?;
+
+        if !eps_meters.is_finite() || eps_meters <= 0.0 {
+            return Err(JsValue::from_str(&format!("eps_meters must be positive, got {}", eps_meters)));
+        }
+        if min_points == 0 {
+            return Err(JsValue::from_str("min_points must be at least 1"));
+        }
+
+        let tree = RTree::bulk_load(
+            points.iter()
+                .enumerate()
+                .map(|(idx, p)| GeomWithData::new(Point::new(p.lng, p.lat), idx))
+                .collect(),
+        );
+        let neighbours = |idx: usize| -> Vec<usize> {
+            let p = &points[idx];
+            let (lat_delta, lng_delta) = degree_deltas(p.lat, eps_meters);
+            let bounds = AABB::from_corners(
+                Point::new(p.lng - lng_delta, p.lat - lat_delta),
+                Point::new(p.lng + lng_delta, p.lat + lat_delta),
+            );
+            tree.locate_in_envelope(&bounds)
+                .map(|candidate| candidate.data)
+                .filter(|&other| {
+                    haversine(p.lat, p.lng, points[other].lat, points[other].lng) <= eps_meters
+                })
+                .collect()
+        };
+
+        // None = unvisited, Some(None) = noise, Some(Some(c)) = cluster c
+        let mut labels: Vec<Option<Option<usize>>> = vec![None; points.len()];
+        let mut cluster_count = 0;
+
+        for idx in 0..points.len() {
+            if labels[idx].is_some() {
+                continue;
+            }
+
+            let seeds = neighbours(idx);
+            if seeds.len() < min_points {
+                labels[idx] = Some(None);
+                continue;
+            }
+
+            let cluster = cluster_count;
+            cluster_count += 1;
+            labels[idx] = Some(Some(cluster));
+
+            let mut queue = seeds;
+            while let Some(other) = queue.pop() {
+                match labels[other] {
+                    // Border point previously taken for noise
+                    Some(None) => labels[other] = Some(Some(cluster)),
+                    None => {
+                        labels[other] = Some(Some(cluster));
+                        let reachable = neighbours(other);
+                        if reachable.len() >= min_points {
+                            queue.extend(reachable);
+                        }
+                    }
+                    Some(Some(_)) => {}
+                }
+            }
+        }
+
+        let mut members: Vec<Vec<usize>> = vec![Vec::new(); cluster_count];
+        let mut noise = Vec::new();
+        for (idx, label) in labels.iter().enumerate() {
+            match label {
+                Some(Some(cluster)) => members[*cluster].push(idx),
+                _ => noise.push(points[idx].id.clone()),
+            }
+        }
+
+        let clusters = members.into_iter()
+            .map(|indices| {
+                let items = indices.iter().map(|&i| points[i].id.clone()).collect();
+                let lats: Vec<f64> = indices.iter().map(|&i| points[i].lat).collect();
+                let lngs: Vec<f64> = indices.iter().map(|&i| points[i].lng).collect();
+                summarize_cluster(items, &lats, &lngs)
+            })
+            .collect();
+
+        serde_json::to_string(&DbscanResult { clusters, noise })
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Partition points into `k` clusters with Lloyd's algorithm, seeded by
+    /// k-means++ from `seed` so results are reproducible. Distances are
+    /// Haversine; centroids are unit-vector centres, as in clusterPoints. If
+    /// `k` exceeds the number of points, each point gets its own cluster and
+    /// the rest are empty.
+    #[wasm_bindgen(js_name = kmeans)]
+    pub fn kmeans(&self, points_json: &str, k: usize, max_iterations: usize, seed: u32) -> Result<String, JsValue> {
+        let points: Vec<PointData> = serde_json::from_str(points_json)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse points: {}", e)))?;
+
+        if k == 0 {
+            return Err(JsValue::from_str("k must be at least 1"));
+        }
+
+        if k >= points.len() {
+            let clusters: Vec<KMeansCluster> = points.iter()
+                .map(|p| KMeansCluster {
+                    centroid: Some(LatLng { lat: p.lat, lng: p.lng }),
+                    count: 1,
+                    items: vec![p.id.clone()],
+                })
+                .chain((points.len()..k).map(|_| KMeansCluster { centroid: None, count: 0, items: Vec::new() }))
+                .collect();
+
+            return serde_json::to_string(&clusters)
+                .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)));
+        }
+
+        let distance_to = |p: &PointData, c: &LatLng| haversine(p.lat, p.lng, c.lat, c.lng);
+        let mut rng = SeededRng(u64::from(seed));
+
+        // k-means++: each further centre is drawn with probability
+        // proportional to its squared distance from the nearest chosen one
+        let first = &points[(rng.next_u64() % points.len() as u64) as usize];
+        let mut centroids = vec![LatLng { lat: first.lat, lng: first.lng }];
+        let mut nearest_sq: Vec<f64> = points.iter()
+            .map(|p| distance_to(p, &centroids[0]).powi(2))
+            .collect();
+        while centroids.len() < k {
+            let total: f64 = nearest_sq.iter().sum();
+            let chosen = if total > 0.0 {
+                let mut target = rng.next_f64() * total;
+                nearest_sq.iter()
+                    .position(|&d| {
+                        target -= d;
+                        target < 0.0
+                    })
+                    .unwrap_or(points.len() - 1)
+            } else {
+                // Every point coincides with a centre already
+                (rng.next_u64() % points.len() as u64) as usize
+            };
+            let centre = LatLng { lat: points[chosen].lat, lng: points[chosen].lng };
+            for (d, p) in nearest_sq.iter_mut().zip(&points) {
+                *d = d.min(distance_to(p, &centre).powi(2));
+            }
+            centroids.push(centre);
+        }
+
+        let nearest_centroid = |p: &PointData, centroids: &[LatLng]| -> usize {
+            centroids.iter()
+                .map(|c| distance_to(p, c))
+                .enumerate()
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(i, _)| i)
+                .unwrap_or(0)
+        };
+
+        let mut assignments: Vec<usize> = points.iter().map(|p| nearest_centroid(p, &centroids)).collect();
+        for _ in 0..max_iterations {
+            // A cluster that lost all its points, or whose points cancel
+            // out, keeps its previous centroid
+            for (cluster, centroid) in centroids.iter_mut().enumerate() {
+                let members = points.iter()
+                    .zip(&assignments)
+                    .filter(|&(_, &assigned)| assigned == cluster)
+                    .map(|(p, _)| (p.lat, p.lng));
+                if let Some(center) = unit_vector_center(members) {
+                    *centroid = center;
+                }
+            }
+
+            let next: Vec<usize> = points.iter().map(|p| nearest_centroid(p, &centroids)).collect();
+            if next == assignments {
+                break;
+            }
+            assignments = next;
+        }
+
+        let mut clusters: Vec<KMeansCluster> = centroids.into_iter()
+            .map(|centroid| KMeansCluster { centroid: Some(centroid), count: 0, items: Vec::new() })
+            .collect();
+        for (p, &cluster) in points.iter().zip(&assignments) {
+            clusters[cluster].items.push(p.id.clone());
+            clusters[cluster].count += 1;
+        }
+
+        serde_json::to_string(&clusters)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Whether segment a1-a2 crosses segment b1-b2, treating lat/lng as
+    /// planar coordinates, with the intersection point or overlap
+    #[wasm_bindgen(js_name = segmentsIntersect)]
+    pub fn segments_intersect(&self, a1_json: &str, a2_json: &str, b1_json: &str, b2_json: &str) -> Result<String, JsValue> {
+        let parse = |json: &str| -> Result<Coord<f64>, JsValue> {
+            let p: LatLng = serde_json::from_str(json)
+                .map_err(|e| JsValue::from_str(&format!("Failed to parse point: {}", e)))?;
+            Ok(Coord { x: p.lng, y: p.lat })
+        };
+        let a = Line::new(parse(a1_json)?, parse(a2_json)?);
+        let b = Line::new(parse(b1_json)?, parse(b2_json)?);
+
+        let to_lat_lng = |c: Coord<f64>| LatLng { lat: c.y, lng: c.x };
+        let shares_endpoint = |c: Coord<f64>| {
+            (c == a.start || c == a.end) && (c == b.start || c == b.end)
+        };
+        let single = |kind: IntersectionKind, c: Coord<f64>| SegmentIntersection {
+            intersects: true,
+            kind,
+            point: Some(to_lat_lng(c)),
+            overlap: None,
+        };
+
+        let result = match line_intersection(a, b) {
+            None => SegmentIntersection {
+                intersects: false,
+                kind: IntersectionKind::None,
+                point: None,
+                overlap: None,
+            },
+            Some(LineIntersection::SinglePoint { intersection, is_proper: true }) => {
+                single(IntersectionKind::Crossing, intersection)
+            }
+            Some(LineIntersection::SinglePoint { intersection, .. }) if shares_endpoint(intersection) => {
+                single(IntersectionKind::SharedEndpoint, intersection)
+            }
+            Some(LineIntersection::SinglePoint { intersection, .. }) => {
+                single(IntersectionKind::Touching, intersection)
+            }
+            // Collinear segments meeting end to end overlap in a single point
+            Some(LineIntersection::Collinear { intersection }) if intersection.start == intersection.end => {
+                single(IntersectionKind::SharedEndpoint, intersection.start)
+            }
+            Some(LineIntersection::Collinear { intersection }) => SegmentIntersection {
+                intersects: true,
+                kind: IntersectionKind::CollinearOverlap,
+                point: None,
+                overlap: Some([to_lat_lng(intersection.start), to_lat_lng(intersection.end)]),
+            },
+        };
+
+        serde_json::to_string(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
     /// Check if two bounding boxes intersect
     #[wasm_bindgen(js_name = bboxIntersects)]
     pub fn bbox_intersects(&self, bbox1_json: &str, bbox2_json: &str) -> Result<bool, JsValue> {
@@ -424,6 +1300,80 @@ impl GeoCalculator {
     pub fn clear(&mut self) {
         self.rtree = None;
         self.polygons.clear();
-        self.cached_distances.clear();
+        self.cached_distances.borrow_mut().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Polygon JSON for the lat/lng box with south-west corner (lat, lng)
+    fn cell(lat: f64, lng: f64, lat_span: f64, lng_span: f64) -> String {
+        format!(
+            r#"{{"coordinates":[{{"lat":{s},"lng":{w}}},{{"lat":{s},"lng":{e}}},{{"lat":{n},"lng":{e}}},{{"lat":{n},"lng":{w}}}]}}"#,
+            s = lat, w = lng, n = lat + lat_span, e = lng + lng_span
+        )
+    }
+
+    #[test]
+    fn multipolygon_area_and_containment_cover_both_parts() {
+        let mut calculator = GeoCalculator::new();
+        let part = cell(51.5, -0.2, 0.01, 0.01);
+        let exclave = cell(51.6, 0.1, 0.01, 0.01);
+        let two_parts = format!("[{},{}]", part, exclave);
+
+        let part_area = calculator.calculate_area(&part).unwrap();
+        let exclave_area = calculator.calculate_area(&exclave).unwrap();
+        let total = calculator.calculate_area(&two_parts).unwrap();
+        assert!((total - (part_area + exclave_area)).abs() < 1e-6, "{}", total);
+
+        calculator.load_polygon("borough", &two_parts).unwrap();
+        assert!(calculator.is_point_in_polygon(51.505, -0.195, "borough"));
+        assert!(calculator.is_point_in_polygon(51.605, 0.105, "borough"));
+        assert!(!calculator.is_point_in_polygon(51.55, -0.05, "borough"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn radius_search_reaches_points_due_east() {
+        let mut calculator = GeoCalculator::new();
+        // ~900 m east of the query point, which a box of radius / 111,320
+        // degrees in longitude would miss at this latitude
+        calculator.build_spatial_index(r#"[{"id":"east","lat":51.5,"lng":-0.087},{"id":"far","lat":51.5,"lng":-0.07}]"#).unwrap();
+
+        let results: serde_json::Value = serde_json::from_str(&calculator.find_within_radius(51.5, -0.1, 1000.0, Some(5)).unwrap()).unwrap();
+        let results = results.as_array().unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["id"], "east");
+        assert!(results[0]["distance_meters"].as_f64().unwrap() > 850.0);
+    }
+
+    #[test]
+    fn geodesic_area_matches_ellipsoidal_reference() {
+        // A borough-sized box over Camden, 51.51-51.57N, 0.21-0.11W. The
+        // reference is the closed-form WGS-84 area between two parallels
+        // and meridians; geodesic edges differ from parallels by ~0.01% here
+        let area = GeoCalculator::new().calculate_area(&cell(51.51, -0.21, 0.06, 0.1)).unwrap();
+        assert!((area / 46_314_534.0 - 1.0).abs() < 1e-3, "{}", area);
+    }
+
+    #[test]
+    fn distance_cache_is_keyed_on_rounded_coordinates() {
+        let calc = GeoCalculator::new();
+        let first = calc.calculate_distance(51.5, -0.15, 51.55, -0.2);
+        assert_eq!(calc.cached_distances.borrow().len(), 1);
+        assert_eq!(calc.calculate_distance(51.5 + 1e-9, -0.15, 51.55, -0.2), first);
+        assert_eq!(calc.cached_distances.borrow().len(), 1);
+        calc.calculate_distance(51.5 + 1e-5, -0.15, 51.55, -0.2);
+        assert_eq!(calc.cached_distances.borrow().len(), 2);
+    }
+
+    #[test]
+    fn kmeans_centroids_straddle_the_antimeridian() {
+        let points = r#"[{"id":"a","lat":10.0,"lng":179.9},{"id":"b","lat":10.0,"lng":-179.9},{"id":"c","lat":10.2,"lng":179.95}]"#;
+        let json = GeoCalculator::new().kmeans(points, 1, 10, 7).unwrap();
+        let clusters: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let lng = clusters[0]["centroid"]["lng"].as_f64().unwrap();
+        assert!(lng.abs() > 179.9, "{}", lng);
+    }
+}
@@ -1,8 +1,9 @@
 use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
 use ahash::AHashMap;
+use std::cmp::Ordering;
 use std::collections::BTreeMap;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Utc};
 
 // Use `wee_alloc` as the global allocator for smaller WASM size
 #[global_allocator]
@@ -45,8 +46,70 @@ pub struct PropertyFilter {
     pub min_area_sqft: Option<f64>,
     pub max_area_sqft: Option<f64>,
     pub features: Option<Vec<String>>,
+    /// Whether a property needs all of `features` or any one of them
+    #[serde(default)]
+    pub features_match: FeaturesMatch,
     pub council_tax_bands: Option<Vec<String>>,
     pub epc_ratings: Option<Vec<String>>,
+    /// Radius search: all three must be given together
+    pub center_lat: Option<f64>,
+    pub center_lng: Option<f64>,
+    pub radius_meters: Option<f64>,
+    /// Inclusive listing date bounds, in any format listing_date accepts
+    pub listed_after: Option<String>,
+    pub listed_before: Option<String>,
+}
+
+/// Centre and radius of a radius filter, with its bounding box precomputed
+struct RadiusFilter {
+    lat: f64,
+    lng: f64,
+    radius_meters: f64,
+    max_lat_delta: f64,
+    max_lng_delta: f64,
+}
+
+impl RadiusFilter {
+    fn from_filter(filter: &PropertyFilter) -> Result<Option<Self>, JsValue> {
+        let (lat, lng, radius_meters) = match (filter.center_lat, filter.center_lng, filter.radius_meters) {
+            (None, None, None) => return Ok(None),
+            (Some(lat), Some(lng), Some(radius)) => (lat, lng, radius),
+            _ => return Err(JsValue::from_str("center_lat, center_lng and radius_meters must be given together")),
+        };
+
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lng) {
+            return Err(JsValue::from_str(&format!("Invalid radius centre: {}, {}", lat, lng)));
+        }
+        if !radius_meters.is_finite() || radius_meters < 0.0 {
+            return Err(JsValue::from_str(&format!("radius_meters must be non-negative, got {}", radius_meters)));
+        }
+
+        // Degrees of latitude/longitude the radius can span; longitude
+        // degrees shrink towards the poles, where the box is skipped
+        let max_lat_delta = (radius_meters / EARTH_RADIUS_METERS).to_degrees();
+        let cos_lat = lat.to_radians().cos();
+        let max_lng_delta = if cos_lat > 1e-6 { max_lat_delta / cos_lat } else { f64::INFINITY };
+
+        Ok(Some(RadiusFilter { lat, lng, radius_meters, max_lat_delta, max_lng_delta }))
+    }
+
+    fn contains(&self, property: &Property) -> bool {
+        let lng_delta = (property.longitude - self.lng).abs();
+        let lng_delta = lng_delta.min(360.0 - lng_delta);
+        if (property.latitude - self.lat).abs() > self.max_lat_delta || lng_delta > self.max_lng_delta {
+            return false;
+        }
+
+        haversine_meters(self.lat, self.lng, property.latitude, property.longitude) <= self.radius_meters
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FeaturesMatch {
+    #[default]
+    All,
+    Any,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -55,6 +118,43 @@ pub struct SortConfig {
     pub ascending: bool,
 }
 
+/// One sort key, or several applied in order as tie-breaks
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SortSpec {
+    Single(SortConfig),
+    Chain(Vec<SortConfig>),
+}
+
+const SORT_FIELDS: &[&str] = &[
+    "price", "bedrooms", "area", "price_per_sqft", "ground_rent", "service_charge", "date",
+    "property_type", "postcode",
+];
+
+/// Parse a SortSpec into its list of keys, rejecting unknown fields
+fn parse_sort_configs(sort_json: &str) -> Result<Vec<SortConfig>, JsValue> {
+    let sort_configs = match serde_json::from_str(sort_json)
+        .map_err(|e| JsValue::from_str(&format!("Failed to parse sort config: {}", e)))?
+    {
+        SortSpec::Single(config) => vec![config],
+        SortSpec::Chain(configs) => configs,
+    };
+
+    if let Some(config) = sort_configs.iter().find(|c| !SORT_FIELDS.contains(&c.field.as_str())) {
+        return Err(JsValue::from_str(&format!("Invalid sort field: {}", config.field)));
+    }
+
+    Ok(sort_configs)
+}
+
+/// One page of queryProperties results
+#[derive(Serialize)]
+struct PropertyPage<'a> {
+    properties: Vec<&'a Property>,
+    /// Number of matches before pagination
+    total: usize,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PropertyStats {
     pub total_count: usize,
@@ -63,18 +163,100 @@ pub struct PropertyStats {
     pub min_price: f64,
     pub max_price: f64,
     pub price_per_sqft: f64,
+    pub price_per_sqm: f64,
+    /// Mean of known floor areas, in `area_unit`
+    pub average_area: Option<f64>,
+    /// "sqft" or "sqm", as requested from calculateStats
+    pub area_unit: String,
     pub average_bedrooms: f64,
     pub property_type_distribution: BTreeMap<String, usize>,
     pub postcode_distribution: BTreeMap<String, usize>,
     pub price_percentiles: BTreeMap<String, f64>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TrendPoint {
+    /// "2024-03" for months, "2024-W09" for ISO weeks
+    pub period: String,
+    pub count: usize,
+    pub average_price: f64,
+    pub median_price: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PriceTrend {
+    pub bucket: String,
+    pub points: Vec<TrendPoint>,
+    /// Properties left out because their listing_date didn't parse
+    pub excluded_count: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PriceOutlier {
+    pub id: String,
+    pub price: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PriceOutliers {
+    pub q1: f64,
+    pub q3: f64,
+    pub iqr: f64,
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+    pub outliers: Vec<PriceOutlier>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AreaUnit {
+    Sqft,
+    Sqm,
+}
+
+impl AreaUnit {
+    fn parse(unit: Option<String>) -> Result<Self, JsValue> {
+        match unit.as_deref() {
+            None | Some("sqft") => Ok(AreaUnit::Sqft),
+            Some("sqm") => Ok(AreaUnit::Sqm),
+            Some(other) => Err(JsValue::from_str(&format!("Invalid area unit: {} (expected sqft or sqm)", other))),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            AreaUnit::Sqft => "sqft",
+            AreaUnit::Sqm => "sqm",
+        }
+    }
+
+    fn convert_sqft(self, sqft: f64) -> f64 {
+        match self {
+            AreaUnit::Sqft => sqft,
+            AreaUnit::Sqm => sqft_to_sqm(sqft),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MortgageRepayment {
+    pub monthly_payment: f64,
+    pub total_interest: f64,
+    pub total_repaid: f64,
+}
+
 #[wasm_bindgen]
 pub struct PropertyProcessor {
     properties: Vec<Property>,
     indexed_by_postcode: AHashMap<String, Vec<usize>>,
     indexed_by_type: AHashMap<String, Vec<usize>>,
     indexed_by_price_range: BTreeMap<u32, Vec<usize>>,
+    indexed_by_bedrooms: BTreeMap<u32, Vec<usize>>,
+    indexed_by_id: AHashMap<String, usize>,
+    // Parsed listing_date per property, None when unparseable
+    listing_dates: Vec<Option<DateTime<Utc>>>,
+    parse_warnings: Vec<String>,
+    // Set by appendProperties until finalizeLoad rebuilds the indexes
+    indexes_stale: bool,
 }
 
 #[wasm_bindgen]
@@ -87,6 +269,11 @@ impl PropertyProcessor {
             indexed_by_postcode: AHashMap::new(),
             indexed_by_type: AHashMap::new(),
             indexed_by_price_range: BTreeMap::new(),
+            indexed_by_bedrooms: BTreeMap::new(),
+            indexed_by_id: AHashMap::new(),
+            listing_dates: Vec::new(),
+            parse_warnings: Vec::new(),
+            indexes_stale: false,
         }
     }
 
@@ -103,13 +290,60 @@ impl PropertyProcessor {
         Ok(count)
     }
 
+    /// Parse a JSON array chunk and append it without rebuilding indexes,
+    /// so large datasets can be streamed in. Call finalizeLoad() once the
+    /// last chunk is in; filtering and lookups fail until then.
+    #[wasm_bindgen(js_name = appendProperties)]
+    pub fn append_properties(&mut self, json_chunk: &str) -> Result<usize, JsValue> {
+        let properties: Vec<Property> = simd_json::from_str(&mut json_chunk.to_string())
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse JSON: {}", e)))?;
+
+        let count = properties.len();
+        self.properties.extend(properties);
+        self.indexes_stale = true;
+
+        Ok(count)
+    }
+
+    /// Build indexes over everything appended, returning the total count
+    #[wasm_bindgen(js_name = finalizeLoad)]
+    pub fn finalize_load(&mut self) -> usize {
+        self.build_indexes();
+        self.properties.len()
+    }
+
+    fn ensure_indexed(&self) -> Result<(), JsValue> {
+        if self.indexes_stale {
+            return Err(JsValue::from_str("Properties were appended; call finalizeLoad() first"));
+        }
+        Ok(())
+    }
+
     /// Build indexes for fast filtering
     fn build_indexes(&mut self) {
         self.indexed_by_postcode.clear();
         self.indexed_by_type.clear();
         self.indexed_by_price_range.clear();
+        self.indexed_by_bedrooms.clear();
+        self.indexed_by_id.clear();
+        self.listing_dates.clear();
+        self.parse_warnings.clear();
+        self.indexes_stale = false;
 
         for (idx, property) in self.properties.iter().enumerate() {
+            // Parse listing date
+            let listing_date = parse_listing_date(&property.listing_date);
+            if listing_date.is_none() {
+                self.parse_warnings.push(format!(
+                    "Property {}: unparseable listing_date '{}'",
+                    property.id, property.listing_date
+                ));
+            }
+            self.listing_dates.push(listing_date);
+
+            // Index by id, keeping the first of any duplicates
+            self.indexed_by_id.entry(property.id.clone()).or_insert(idx);
+
             // Index by postcode prefix
             let postcode_prefix = property.postcode.split_whitespace()
                 .next()
@@ -132,14 +366,67 @@ impl PropertyProcessor {
                 .entry(price_bucket)
                 .or_insert_with(Vec::new)
                 .push(idx);
+
+            // Index by bedroom count
+            self.indexed_by_bedrooms
+                .entry(property.bedrooms)
+                .or_default()
+                .push(idx);
         }
     }
 
     /// Filter properties using WASM-optimized logic
     #[wasm_bindgen(js_name = filterProperties)]
     pub fn filter_properties(&self, filter_json: &str) -> Result<String, JsValue> {
+        self.ensure_indexed()?;
+        let result_indices = self.matching_indices(filter_json)?;
+
+        let filtered: Vec<&Property> = result_indices
+            .iter()
+            .map(|&idx| &self.properties[idx])
+            .collect();
+
+        serde_json::to_string(&filtered)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Filter, then sort the matches by a SortConfig or chain of them (as in
+    /// sortProperties), then return `limit` of them starting at `offset`
+    /// along with the total match count. The stored order is left unchanged
+    #[wasm_bindgen(js_name = queryProperties)]
+    pub fn query_properties(
+        &self,
+        filter_json: &str,
+        sort_json: &str,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Result<String, JsValue> {
+        self.ensure_indexed()?;
+        let sort_configs = parse_sort_configs(sort_json)?;
+        let mut result_indices = self.matching_indices(filter_json)?;
+        self.sort_indices(&mut result_indices, &sort_configs, &self.listing_dates);
+
+        let page = PropertyPage {
+            total: result_indices.len(),
+            properties: result_indices
+                .iter()
+                .skip(offset)
+                .take(limit.unwrap_or(usize::MAX))
+                .map(|&idx| &self.properties[idx])
+                .collect(),
+        };
+
+        serde_json::to_string(&page)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Indices of properties matching `filter_json`, in stored order
+    fn matching_indices(&self, filter_json: &str) -> Result<Vec<usize>, JsValue> {
         let filter: PropertyFilter = serde_json::from_str(filter_json)
             .map_err(|e| JsValue::from_str(&format!("Failed to parse filter: {}", e)))?;
+        let radius = RadiusFilter::from_filter(&filter)?;
+        let listed_after = parse_filter_date(&filter.listed_after, "listed_after")?;
+        let listed_before = parse_filter_date(&filter.listed_before, "listed_before")?;
 
         let mut result_indices = Vec::new();
 
@@ -149,18 +436,15 @@ impl PropertyProcessor {
         for &idx in &candidates {
             let property = &self.properties[idx];
 
-            if self.property_matches_filter(property, &filter) {
+            if self.property_matches_filter(property, &filter)
+                && radius.as_ref().is_none_or(|radius| radius.contains(property))
+                && self.listed_within(idx, listed_after, listed_before)
+            {
                 result_indices.push(idx);
             }
         }
 
-        let filtered: Vec<&Property> = result_indices
-            .iter()
-            .map(|&idx| &self.properties[idx])
-            .collect();
-
-        serde_json::to_string(&filtered)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+        Ok(result_indices)
     }
 
     /// Get candidate indices using indexes
@@ -205,6 +489,19 @@ impl PropertyProcessor {
             }
         }
 
+        // Use bedrooms index if specified
+        if use_full_scan && (filter.min_bedrooms.is_some() || filter.max_bedrooms.is_some()) {
+            use_full_scan = false;
+            let min_bedrooms = filter.min_bedrooms.unwrap_or(0);
+            let max_bedrooms = filter.max_bedrooms.unwrap_or(u32::MAX);
+
+            if min_bedrooms <= max_bedrooms {
+                for (_, indices) in self.indexed_by_bedrooms.range(min_bedrooms..=max_bedrooms) {
+                    candidates.extend(indices);
+                }
+            }
+        }
+
         // If no specific indexes used, scan all
         if use_full_scan {
             candidates = (0..self.properties.len()).collect();
@@ -216,6 +513,19 @@ impl PropertyProcessor {
         candidates
     }
 
+    /// Whether a property's listing date falls within the bounds. A property
+    /// with an unparseable date fails any bound.
+    fn listed_within(&self, idx: usize, after: Option<DateTime<Utc>>, before: Option<DateTime<Utc>>) -> bool {
+        if after.is_none() && before.is_none() {
+            return true;
+        }
+
+        match self.listing_dates[idx] {
+            Some(date) => after.is_none_or(|after| date >= after) && before.is_none_or(|before| date <= before),
+            None => false,
+        }
+    }
+
     /// Check if property matches filter criteria
     fn property_matches_filter(&self, property: &Property, filter: &PropertyFilter) -> bool {
         // Price filter
@@ -242,124 +552,147 @@ impl PropertyProcessor {
             }
         }
 
-        // Area filter
-        if let Some(area) = property.area_sqft {
-            if let Some(min) = filter.min_area_sqft {
-                if area < min {
-                    return false;
-                }
+        // Area filter - a property with no area fails any area bound
+        if let Some(min) = filter.min_area_sqft {
+            if !property.area_sqft.is_some_and(|area| area >= min) {
+                return false;
             }
-            if let Some(max) = filter.max_area_sqft {
-                if area > max {
-                    return false;
-                }
+        }
+        if let Some(max) = filter.max_area_sqft {
+            if !property.area_sqft.is_some_and(|area| area <= max) {
+                return false;
             }
         }
 
-        // Features filter - property must have all requested features
-        if let Some(required_features) = &filter.features {
-            for feature in required_features {
-                if !property.features.iter().any(|f| f.eq_ignore_ascii_case(feature)) {
-                    return false;
-                }
+        // Features filter - property must have all (or, with "any", at
+        // least one) of the requested features
+        if let Some(requested_features) = &filter.features {
+            let has_feature = |feature: &String| property.features.iter().any(|f| f.eq_ignore_ascii_case(feature));
+            let matched = match filter.features_match {
+                FeaturesMatch::All => requested_features.iter().all(has_feature),
+                FeaturesMatch::Any => requested_features.iter().any(has_feature),
+            };
+            if !matched {
+                return false;
             }
         }
 
-        // Council tax band filter
+        // Council tax band filter - a property with no band is excluded
         if let Some(bands) = &filter.council_tax_bands {
-            if let Some(band) = &property.council_tax_band {
-                if !bands.iter().any(|b| b.eq_ignore_ascii_case(band)) {
-                    return false;
-                }
+            match &property.council_tax_band {
+                Some(band) if bands.iter().any(|b| b.eq_ignore_ascii_case(band)) => {}
+                _ => return false,
             }
         }
 
-        // EPC rating filter
+        // EPC rating filter - a property with no rating is excluded
         if let Some(ratings) = &filter.epc_ratings {
-            if let Some(rating) = &property.epc_rating {
-                if !ratings.iter().any(|r| r.eq_ignore_ascii_case(rating)) {
-                    return false;
-                }
+            match &property.epc_rating {
+                Some(rating) if ratings.iter().any(|r| r.eq_ignore_ascii_case(rating)) => {}
+                _ => return false,
             }
         }
 
         true
     }
 
-    /// Sort properties with WASM-optimized algorithms
+    /// Sort properties by a SortConfig, or an array of them where each
+    /// later key breaks ties left by the earlier ones; equal keys keep order
     #[wasm_bindgen(js_name = sortProperties)]
     pub fn sort_properties(&mut self, sort_json: &str) -> Result<String, JsValue> {
-        let sort_config: SortConfig = serde_json::from_str(sort_json)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse sort config: {}", e)))?;
-
-        match sort_config.field.as_str() {
-            "price" => {
-                if sort_config.ascending {
-                    self.properties.sort_by(|a, b| a.price.partial_cmp(&b.price).unwrap());
-                } else {
-                    self.properties.sort_by(|a, b| b.price.partial_cmp(&a.price).unwrap());
-                }
-            }
-            "bedrooms" => {
-                if sort_config.ascending {
-                    self.properties.sort_by_key(|p| p.bedrooms);
-                } else {
-                    self.properties.sort_by_key(|p| std::cmp::Reverse(p.bedrooms));
-                }
-            }
-            "area" => {
-                self.properties.sort_by(|a, b| {
-                    let a_area = a.area_sqft.unwrap_or(0.0);
-                    let b_area = b.area_sqft.unwrap_or(0.0);
-                    if sort_config.ascending {
-                        a_area.partial_cmp(&b_area).unwrap()
-                    } else {
-                        b_area.partial_cmp(&a_area).unwrap()
-                    }
-                });
-            }
-            "date" => {
-                if sort_config.ascending {
-                    self.properties.sort_by(|a, b| a.listing_date.cmp(&b.listing_date));
-                } else {
-                    self.properties.sort_by(|a, b| b.listing_date.cmp(&a.listing_date));
-                }
-            }
-            _ => return Err(JsValue::from_str("Invalid sort field")),
-        }
+        let sort_configs = parse_sort_configs(sort_json)?;
+
+        // Sort positions, then reorder the properties to match
+        let dates: Vec<Option<DateTime<Utc>>> = self.properties.iter()
+            .map(|p| parse_listing_date(&p.listing_date))
+            .collect();
+        let mut order: Vec<usize> = (0..self.properties.len()).collect();
+        self.sort_indices(&mut order, &sort_configs, &dates);
+
+        let mut slots: Vec<Option<Property>> = std::mem::take(&mut self.properties).into_iter().map(Some).collect();
+        self.properties = order.into_iter().filter_map(|idx| slots[idx].take()).collect();
+
+        // Indexes and parsed dates are positional, so follow the new order
+        self.build_indexes();
 
         serde_json::to_string(&self.properties)
             .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
 
-    /// Calculate property statistics
+    /// Stable-sort property positions, later configs breaking ties left by
+    /// earlier ones
+    fn sort_indices(&self, indices: &mut [usize], sort_configs: &[SortConfig], dates: &[Option<DateTime<Utc>>]) {
+        indices.sort_by(|&a, &b| {
+            sort_configs.iter()
+                .map(|config| self.compare_by(config, a, b, dates))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+    }
+
+    /// Compare properties `a` and `b` by one sort key; missing values sort
+    /// last in either direction
+    fn compare_by(&self, config: &SortConfig, a: usize, b: usize, dates: &[Option<DateTime<Utc>>]) -> Ordering {
+        let (pa, pb) = (&self.properties[a], &self.properties[b]);
+        let ascending = config.ascending;
+        let direction = |ordering: Ordering| if ascending { ordering } else { ordering.reverse() };
+
+        match config.field.as_str() {
+            "price" => compare_optional(Some(pa.price), Some(pb.price), ascending),
+            "bedrooms" => direction(pa.bedrooms.cmp(&pb.bedrooms)),
+            "area" => compare_optional(pa.area_sqft, pb.area_sqft, ascending),
+            "price_per_sqft" => compare_optional(price_per_sqft(pa), price_per_sqft(pb), ascending),
+            "ground_rent" => compare_optional(pa.ground_rent, pb.ground_rent, ascending),
+            "service_charge" => compare_optional(pa.service_charge, pb.service_charge, ascending),
+            "date" => match (dates[a], dates[b]) {
+                (Some(da), Some(db)) => direction(da.cmp(&db)),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+            "property_type" => direction(pa.property_type.to_lowercase().cmp(&pb.property_type.to_lowercase())),
+            "postcode" => direction(pa.postcode.to_uppercase().cmp(&pb.postcode.to_uppercase())),
+            _ => Ordering::Equal,
+        }
+    }
+
+    /// Calculate property statistics, reporting areas in `area_unit`
+    /// ("sqft" by default, or "sqm")
     #[wasm_bindgen(js_name = calculateStats)]
-    pub fn calculate_stats(&self) -> Result<String, JsValue> {
+    pub fn calculate_stats(&self, area_unit: Option<String>) -> Result<String, JsValue> {
+        let unit = AreaUnit::parse(area_unit)?;
         if self.properties.is_empty() {
             return Err(JsValue::from_str("No properties loaded"));
         }
 
-        let mut prices: Vec<f64> = self.properties.iter().map(|p| p.price).collect();
+        let properties: Vec<&Property> = self.properties.iter().collect();
+        let stats = Self::compute_stats(&properties, unit);
+
+        serde_json::to_string(&stats)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Stats over a non-empty set of properties
+    fn compute_stats(properties: &[&Property], unit: AreaUnit) -> PropertyStats {
+        let mut prices: Vec<f64> = properties.iter().map(|p| p.price).collect();
         prices.sort_by(|a, b| a.partial_cmp(b).unwrap());
 
-        let total_count = self.properties.len();
+        let total_count = properties.len();
         let sum: f64 = prices.iter().sum();
         let average_price = sum / total_count as f64;
-        let median_price = if total_count % 2 == 0 {
-            (prices[total_count / 2 - 1] + prices[total_count / 2]) / 2.0
-        } else {
-            prices[total_count / 2]
-        };
+        let median_price = median(&prices);
 
         let min_price = prices[0];
         let max_price = prices[total_count - 1];
 
         // Calculate price per sqft
         let mut price_per_sqft_values = Vec::new();
-        for property in &self.properties {
+        let mut areas = Vec::new();
+        for property in properties {
             if let Some(area) = property.area_sqft {
                 if area > 0.0 {
                     price_per_sqft_values.push(property.price / area);
+                    areas.push(area);
                 }
             }
         }
@@ -368,20 +701,24 @@ impl PropertyProcessor {
         } else {
             0.0
         };
+        // One sqm holds SQFT_PER_SQM sqft
+        let price_per_sqm = price_per_sqft * SQFT_PER_SQM;
+        let average_area = (!areas.is_empty())
+            .then(|| unit.convert_sqft(areas.iter().sum::<f64>() / areas.len() as f64));
 
         // Calculate average bedrooms
-        let total_bedrooms: u32 = self.properties.iter().map(|p| p.bedrooms).sum();
+        let total_bedrooms: u32 = properties.iter().map(|p| p.bedrooms).sum();
         let average_bedrooms = total_bedrooms as f64 / total_count as f64;
 
         // Property type distribution
         let mut property_type_distribution = BTreeMap::new();
-        for property in &self.properties {
+        for property in properties {
             *property_type_distribution.entry(property.property_type.clone()).or_insert(0) += 1;
         }
 
         // Postcode distribution
         let mut postcode_distribution = BTreeMap::new();
-        for property in &self.properties {
+        for property in properties {
             let prefix = property.postcode.split_whitespace()
                 .next()
                 .unwrap_or(&property.postcode)
@@ -391,25 +728,149 @@ impl PropertyProcessor {
 
         // Calculate percentiles
         let mut price_percentiles = BTreeMap::new();
-        for percentile in &[10, 25, 50, 75, 90] {
-            let index = (total_count as f64 * (*percentile as f64 / 100.0)) as usize;
-            let index = index.min(total_count - 1);
-            price_percentiles.insert(format!("p{}", percentile), prices[index]);
+        for percentile in [10, 25, 50, 75, 90] {
+            price_percentiles.insert(format!("p{}", percentile), percentile_of(&prices, percentile));
         }
 
-        let stats = PropertyStats {
+        PropertyStats {
             total_count,
             average_price,
             median_price,
             min_price,
             max_price,
             price_per_sqft,
+            price_per_sqm,
+            average_area,
+            area_unit: unit.name().to_string(),
             average_bedrooms,
             property_type_distribution,
             postcode_distribution,
             price_percentiles,
+        }
+    }
+
+    /// Price count, average and median per calendar "month" or ISO "week"
+    /// of listing date, oldest first. Properties whose listing_date doesn't
+    /// parse are left out and counted in `excluded_count`
+    #[wasm_bindgen(js_name = priceTrend)]
+    pub fn price_trend(&self, bucket: &str) -> Result<String, JsValue> {
+        self.ensure_indexed()?;
+        if bucket != "month" && bucket != "week" {
+            return Err(JsValue::from_str(&format!("Invalid trend bucket: {} (expected month or week)", bucket)));
+        }
+
+        // Keyed by (year, month) or (ISO year, ISO week) so buckets sort chronologically
+        let mut buckets: BTreeMap<(i32, u32), Vec<f64>> = BTreeMap::new();
+        let mut excluded_count = 0;
+        for (property, date) in self.properties.iter().zip(&self.listing_dates) {
+            let Some(date) = date else {
+                excluded_count += 1;
+                continue;
+            };
+            let key = if bucket == "month" {
+                (date.year(), date.month())
+            } else {
+                let week = date.iso_week();
+                (week.year(), week.week())
+            };
+            buckets.entry(key).or_default().push(property.price);
+        }
+
+        let points = buckets
+            .into_iter()
+            .map(|((year, period), mut prices)| {
+                prices.sort_by(|a, b| a.total_cmp(b));
+                TrendPoint {
+                    period: if bucket == "month" {
+                        format!("{}-{:02}", year, period)
+                    } else {
+                        format!("{}-W{:02}", year, period)
+                    },
+                    count: prices.len(),
+                    average_price: prices.iter().sum::<f64>() / prices.len() as f64,
+                    median_price: median(&prices),
+                }
+            })
+            .collect();
+
+        let trend = PriceTrend {
+            bucket: bucket.to_string(),
+            points,
+            excluded_count,
+        };
+
+        serde_json::to_string(&trend)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Properties priced below Q1 - multiplier * IQR or above
+    /// Q3 + multiplier * IQR, using the p25/p75 of calculateStats as the
+    /// quartiles. `multiplier` defaults to 1.5
+    #[wasm_bindgen(js_name = priceOutliers)]
+    pub fn price_outliers(&self, multiplier: Option<f64>) -> Result<String, JsValue> {
+        let multiplier = multiplier.unwrap_or(1.5);
+        if !multiplier.is_finite() || multiplier < 0.0 {
+            return Err(JsValue::from_str("Outlier multiplier must be a non-negative number"));
+        }
+        if self.properties.is_empty() {
+            return Err(JsValue::from_str("No properties loaded"));
+        }
+
+        let mut prices: Vec<f64> = self.properties.iter().map(|p| p.price).collect();
+        prices.sort_by(|a, b| a.total_cmp(b));
+
+        let q1 = percentile_of(&prices, 25);
+        let q3 = percentile_of(&prices, 75);
+        let iqr = q3 - q1;
+        let lower_bound = q1 - multiplier * iqr;
+        let upper_bound = q3 + multiplier * iqr;
+
+        let outliers = self.properties
+            .iter()
+            .filter(|p| p.price < lower_bound || p.price > upper_bound)
+            .map(|p| PriceOutlier { id: p.id.clone(), price: p.price })
+            .collect();
+
+        let report = PriceOutliers {
+            q1,
+            q3,
+            iqr,
+            lower_bound,
+            upper_bound,
+            outliers,
         };
 
+        serde_json::to_string(&report)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Stats per group, where `field` is "postcode" (outward code),
+    /// "property_type", "council_tax_band" or "bedrooms". Properties with
+    /// no council tax band are grouped under "unknown".
+    #[wasm_bindgen(js_name = calculateStatsByGroup)]
+    pub fn calculate_stats_by_group(&self, field: &str, area_unit: Option<String>) -> Result<String, JsValue> {
+        let unit = AreaUnit::parse(area_unit)?;
+        let mut groups: BTreeMap<String, Vec<&Property>> = BTreeMap::new();
+
+        for property in &self.properties {
+            let key = match field {
+                "postcode" => property.postcode.split_whitespace()
+                    .next()
+                    .unwrap_or(&property.postcode)
+                    .to_uppercase(),
+                "property_type" => property.property_type.clone(),
+                "council_tax_band" => property.council_tax_band.clone().unwrap_or_else(|| "unknown".to_string()),
+                "bedrooms" => property.bedrooms.to_string(),
+                _ => return Err(JsValue::from_str(&format!("Invalid group field: {}", field))),
+            };
+            groups.entry(key).or_default().push(property);
+        }
+
+        let stats: BTreeMap<String, PropertyStats> = groups
+            .into_iter()
+            .map(|(key, properties)| (key, Self::compute_stats(&properties, unit)))
+            .collect();
+
         serde_json::to_string(&stats)
             .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
@@ -440,6 +901,122 @@ impl PropertyProcessor {
             .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
 
+    /// Batch transform properties with a single call to the transform function,
+    /// which receives the whole property array and must return an array of the
+    /// same length
+    #[wasm_bindgen(js_name = batchTransformCollect)]
+    pub fn batch_transform_collect(&mut self, transform_fn: &js_sys::Function) -> Result<String, JsValue> {
+        let this = JsValue::null();
+
+        let properties_json = serde_json::to_string(&self.properties)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))?;
+
+        let result = transform_fn.call1(&this, &JsValue::from_str(&properties_json))?;
+
+        let result_str = result.as_string()
+            .ok_or_else(|| JsValue::from_str("Transform function must return a string"))?;
+
+        let items: Vec<serde_json::Value> = serde_json::from_str(&result_str)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse transformed properties: {}", e)))?;
+
+        if items.len() != self.properties.len() {
+            return Err(JsValue::from_str(&format!(
+                "Transform function returned {} properties, expected {}",
+                items.len(),
+                self.properties.len()
+            )));
+        }
+
+        let mut transformed = Vec::with_capacity(items.len());
+        let mut errors = Vec::new();
+
+        for (idx, item) in items.into_iter().enumerate() {
+            match serde_json::from_value::<Property>(item) {
+                Ok(property) => transformed.push(property),
+                Err(e) => errors.push(format!("Property {}: {}", idx, e)),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(JsValue::from_str(&format!(
+                "Failed to parse transformed properties: {}",
+                errors.join("; ")
+            )));
+        }
+
+        self.properties = transformed;
+
+        // Rebuild indexes after transformation
+        self.build_indexes();
+
+        serde_json::to_string(&self.properties)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Get a single property by id
+    #[wasm_bindgen(js_name = getPropertyById)]
+    pub fn get_property_by_id(&self, id: &str) -> Result<String, JsValue> {
+        self.ensure_indexed()?;
+        let idx = self.indexed_by_id.get(id)
+            .ok_or_else(|| JsValue::from_str(&format!("Property not found: {}", id)))?;
+
+        serde_json::to_string(&self.properties[*idx])
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Remove a property by id, returning whether it existed
+    #[wasm_bindgen(js_name = removePropertyById)]
+    pub fn remove_property_by_id(&mut self, id: &str) -> bool {
+        if self.indexes_stale {
+            self.build_indexes();
+        }
+        let Some(&idx) = self.indexed_by_id.get(id) else {
+            return false;
+        };
+
+        self.properties.remove(idx);
+
+        // Every later position shifts down, so rebuild rather than patch
+        self.build_indexes();
+        true
+    }
+
+    /// Warnings from the last load, e.g. listing dates that couldn't be parsed
+    #[wasm_bindgen(js_name = getParseWarnings)]
+    pub fn get_parse_warnings(&self) -> Result<String, JsValue> {
+        serde_json::to_string(&self.parse_warnings)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Export property index statistics. Sizes reflect the last build, so
+    /// `indexes_stale` is true while appended properties await finalizeLoad
+    #[wasm_bindgen(js_name = getIndexStats)]
+    pub fn get_index_stats(&self) -> Result<String, JsValue> {
+        #[derive(Serialize)]
+        struct IndexStats {
+            total_properties: usize,
+            postcode_prefixes: usize,
+            property_types: usize,
+            price_buckets: usize,
+            min_price_bucket: Option<u32>,
+            max_price_bucket: Option<u32>,
+            indexes_stale: bool,
+        }
+
+        let stats = IndexStats {
+            total_properties: self.properties.len(),
+            postcode_prefixes: self.indexed_by_postcode.len(),
+            property_types: self.indexed_by_type.len(),
+            price_buckets: self.indexed_by_price_range.len(),
+            min_price_bucket: self.indexed_by_price_range.keys().next().copied(),
+            max_price_bucket: self.indexed_by_price_range.keys().next_back().copied(),
+            indexes_stale: self.indexes_stale,
+        };
+
+        serde_json::to_string(&stats)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
     /// Get properties count
     #[wasm_bindgen(js_name = getCount)]
     pub fn get_count(&self) -> usize {
@@ -453,6 +1030,90 @@ impl PropertyProcessor {
         self.indexed_by_postcode.clear();
         self.indexed_by_type.clear();
         self.indexed_by_price_range.clear();
+        self.indexed_by_bedrooms.clear();
+        self.indexed_by_id.clear();
+        self.listing_dates.clear();
+        self.parse_warnings.clear();
+        self.indexes_stale = false;
+    }
+}
+
+/// Accepts RFC 3339, YYYY-MM-DDTHH:MM:SS (taken as UTC) or YYYY-MM-DD
+/// (midnight UTC)
+fn parse_listing_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok()
+        .or_else(|| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S").ok().map(|dt| dt.and_utc()))
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .map(|dt| dt.and_utc())
+        })
+}
+
+/// Median of an ascending, non-empty slice
+fn median(sorted: &[f64]) -> f64 {
+    let len = sorted.len();
+    if len.is_multiple_of(2) {
+        (sorted[len / 2 - 1] + sorted[len / 2]) / 2.0
+    } else {
+        sorted[len / 2]
+    }
+}
+
+/// Nearest-rank percentile of an ascending, non-empty slice
+fn percentile_of(sorted: &[f64], percentile: u32) -> f64 {
+    let index = (sorted.len() as f64 * (percentile as f64 / 100.0)) as usize;
+    sorted[index.min(sorted.len() - 1)]
+}
+
+fn parse_filter_date(value: &Option<String>, name: &str) -> Result<Option<DateTime<Utc>>, JsValue> {
+    value.as_deref()
+        .map(|v| parse_listing_date(v).ok_or_else(|| JsValue::from_str(&format!("Invalid {}: {}", name, v))))
+        .transpose()
+}
+
+const SQFT_PER_SQM: f64 = 10.763_910_416_709_722;
+
+#[wasm_bindgen(js_name = sqftToSqm)]
+pub fn sqft_to_sqm(sqft: f64) -> f64 {
+    sqft / SQFT_PER_SQM
+}
+
+#[wasm_bindgen(js_name = sqmToSqft)]
+pub fn sqm_to_sqft(sqm: f64) -> f64 {
+    sqm * SQFT_PER_SQM
+}
+
+const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+/// Great-circle distance between two points in degrees
+fn haversine_meters(lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> f64 {
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lng = (lng2 - lng1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2)
+        + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lng / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METERS * a.sqrt().min(1.0).asin()
+}
+
+/// Price divided by floor area, when the area is known and positive
+fn price_per_sqft(property: &Property) -> Option<f64> {
+    property.area_sqft
+        .filter(|&area| area > 0.0)
+        .map(|area| property.price / area)
+}
+
+/// Orders two optional values, always putting missing (or NaN) values last
+fn compare_optional(a: Option<f64>, b: Option<f64>, ascending: bool) -> Ordering {
+    match (a.filter(|v| !v.is_nan()), b.filter(|v| !v.is_nan())) {
+        (Some(a), Some(b)) if ascending => a.total_cmp(&b),
+        (Some(a), Some(b)) => b.total_cmp(&a),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
     }
 }
 
@@ -472,4 +1133,93 @@ pub fn validate_property_data(json: &str) -> Result<bool, JsValue> {
         Ok(_) => Ok(true),
         Err(e) => Ok(false),
     }
-}
\ No newline at end of file
+}
+
+/// Annual rent as a percentage of the purchase price
+#[wasm_bindgen(js_name = calculateGrossYield)]
+pub fn calculate_gross_yield(price: f64, monthly_rent: f64) -> Result<f64, JsValue> {
+    if !(price.is_finite() && price > 0.0) {
+        return Err(JsValue::from_str(&format!("Price must be positive, got {}", price)));
+    }
+    if !(monthly_rent.is_finite() && monthly_rent > 0.0) {
+        return Err(JsValue::from_str(&format!("Monthly rent must be positive, got {}", monthly_rent)));
+    }
+
+    Ok(monthly_rent * 12.0 / price * 100.0)
+}
+
+/// Repayment mortgage with monthly compounding; a 0% rate repays the
+/// principal in equal instalments
+#[wasm_bindgen(js_name = calculateMortgage)]
+pub fn calculate_mortgage(principal: f64, annual_rate_pct: f64, term_years: u32) -> Result<String, JsValue> {
+    if !(principal.is_finite() && principal > 0.0) {
+        return Err(JsValue::from_str(&format!("Principal must be positive, got {}", principal)));
+    }
+    if !(annual_rate_pct.is_finite() && annual_rate_pct >= 0.0) {
+        return Err(JsValue::from_str(&format!("Interest rate must be non-negative, got {}", annual_rate_pct)));
+    }
+    if term_years == 0 {
+        return Err(JsValue::from_str("Term must be at least one year"));
+    }
+
+    let months = f64::from(term_years) * 12.0;
+    let monthly_rate = annual_rate_pct / 100.0 / 12.0;
+    let monthly_payment = if monthly_rate == 0.0 {
+        principal / months
+    } else {
+        let growth = (1.0 + monthly_rate).powf(months);
+        principal * monthly_rate * growth / (growth - 1.0)
+    };
+    let total_repaid = monthly_payment * months;
+
+    let repayment = MortgageRepayment {
+        monthly_payment,
+        total_interest: total_repaid - principal,
+        total_repaid,
+    };
+
+    serde_json::to_string(&repayment)
+        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn property(id: &str, epc_rating: Option<&str>) -> serde_json::Value {
+        serde_json::json!({
+            "id": id,
+            "address": "1 High Street",
+            "postcode": "NW3 1AA",
+            "price": 500000.0,
+            "bedrooms": 2,
+            "bathrooms": 1,
+            "property_type": "flat",
+            "area_sqft": 700.0,
+            "latitude": 51.55,
+            "longitude": -0.17,
+            "listing_date": "2024-01-01",
+            "features": [],
+            "council_tax_band": "D",
+            "epc_rating": epc_rating,
+            "tenure": "leasehold",
+            "ground_rent": null,
+            "service_charge": null
+        })
+    }
+
+    fn filtered_ids(processor: &PropertyProcessor, filter_json: &str) -> Vec<String> {
+        let properties: Vec<Property> = serde_json::from_str(&processor.filter_properties(filter_json).unwrap()).unwrap();
+        properties.into_iter().map(|p| p.id).collect()
+    }
+
+    #[test]
+    fn epc_filter_excludes_properties_without_a_rating() {
+        let mut processor = PropertyProcessor::new();
+        let properties = serde_json::json!([property("rated", Some("B")), property("unrated", None)]);
+        processor.load_properties(&properties.to_string()).unwrap();
+
+        assert_eq!(filtered_ids(&processor, r#"{"epc_ratings": ["A", "B"]}"#), ["rated"]);
+        assert_eq!(filtered_ids(&processor, "{}"), ["rated", "unrated"]);
+    }
+}
@@ -1,9 +1,34 @@
 use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
 use ahash::{AHashMap, AHashSet};
+use std::cmp::{Ordering, Reverse};
 use std::collections::BTreeMap;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use rust_stemmers::{Algorithm, Stemmer};
 use web_sys::console;
 
+const DEFAULT_SNIPPET_LENGTH: usize = 160;
+const DEFAULT_BM25_K1: f64 = 1.2;
+const SPELLING_MAX_DISTANCE: usize = 2;
+const DEFAULT_BM25_B: f64 = 0.75;
+
+// Dropped from documents and queries unless replaced via setStopWords
+const DEFAULT_STOP_WORDS: &[&str] = &[
+    "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "has", "have",
+    "he", "her", "his", "if", "in", "into", "is", "it", "its", "of", "on", "or",
+    "our", "she", "so", "than", "that", "the", "their", "them", "then", "there",
+    "these", "they", "this", "to", "was", "we", "were", "which", "who", "will",
+    "with", "you", "your",
+];
+
+// A `lond*` query term expands to at most this many index terms, chosen by
+// document count, so very short prefixes can't pull in the whole vocabulary
+const MAX_PREFIX_EXPANSIONS: usize = 50;
+
+fn default_stop_words() -> AHashSet<String> {
+    DEFAULT_STOP_WORDS.iter().map(|word| word.to_string()).collect()
+}
+
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
@@ -21,6 +46,15 @@ pub struct SearchDocument {
     pub category: String,
     pub score: f64,
     pub metadata: Option<AHashMap<String, String>>,
+    /// Date used by date_range filters, falling back to metadata["date"].
+    /// Accepts YYYY-MM-DD, RFC 3339 or YYYY-MM-DDTHH:MM:SS.
+    #[serde(default)]
+    pub date: Option<String>,
+    #[serde(default)]
+    pub matched_terms: Vec<String>,
+    /// Highlighted content excerpt, set when the query asks for one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -32,6 +66,49 @@ pub struct SearchQuery {
     pub boost_fields: Option<AHashMap<String, f64>>,
     pub fuzzy: bool,
     pub fuzzy_distance: Option<usize>,
+    /// Minimum number of distinct query terms a document must match,
+    /// capped at the number of terms in the query
+    pub min_should_match: Option<usize>,
+    /// Jaccard similarity (over title + content tokens) at or above which
+    /// two results are treated as duplicates
+    pub dedup_threshold: Option<f64>,
+    /// BM25 term-frequency saturation, defaults to 1.2
+    pub k1: Option<f64>,
+    /// BM25 document-length normalisation in [0, 1], defaults to 0.75
+    pub b: Option<f64>,
+    /// Attach a content snippet with matched terms wrapped in <mark>
+    #[serde(default)]
+    pub highlight: bool,
+    /// Snippet length in characters, defaults to 160
+    pub snippet_length: Option<usize>,
+    /// Order results by a field instead of relevance
+    pub sort: Option<SortSpec>,
+}
+
+/// `field` is "score", "title", "category" or a metadata key holding a
+/// number. Documents without that number go last; ties keep relevance order.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SortSpec {
+    pub field: String,
+    #[serde(default = "default_ascending")]
+    pub ascending: bool,
+}
+
+fn default_ascending() -> bool {
+    true
+}
+
+/// Fills in default BM25 parameters and rejects out-of-range ones
+fn bm25_params(k1: Option<f64>, b: Option<f64>) -> Result<(f64, f64), JsValue> {
+    let k1 = k1.unwrap_or(DEFAULT_BM25_K1);
+    let b = b.unwrap_or(DEFAULT_BM25_B);
+    if !k1.is_finite() || k1 < 0.0 {
+        return Err(JsValue::from_str(&format!("k1 must be a non-negative number, got {}", k1)));
+    }
+    if !(0.0..=1.0).contains(&b) {
+        return Err(JsValue::from_str(&format!("b must be between 0 and 1, got {}", b)));
+    }
+    Ok((k1, b))
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -40,61 +117,583 @@ pub struct SearchFilters {
     pub tags: Option<Vec<String>>,
     pub date_range: Option<DateRange>,
     pub score_threshold: Option<f64>,
+    pub metadata_filters: Option<MetadataFilters>,
+}
+
+/// Either a list of rules or, as before, a map of exact key/value matches
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum MetadataFilters {
+    Rules(Vec<MetadataFilter>),
+    Exact(AHashMap<String, String>),
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MetadataFilter {
+    pub key: String,
+    pub operator: MetadataOperator,
+    pub value: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataOperator {
+    Equals,
+    NotEquals,
+    Contains,
+    StartsWith,
+    EndsWith,
 }
 
+impl MetadataFilter {
+    /// Documents lacking the key never match, whatever the operator
+    fn matches(&self, metadata: Option<&AHashMap<String, String>>) -> bool {
+        let Some(actual) = metadata.and_then(|metadata| metadata.get(&self.key)) else {
+            return false;
+        };
+
+        match self.operator {
+            MetadataOperator::Equals => *actual == self.value,
+            MetadataOperator::NotEquals => *actual != self.value,
+            MetadataOperator::Contains => actual.contains(&self.value),
+            MetadataOperator::StartsWith => actual.starts_with(&self.value),
+            MetadataOperator::EndsWith => actual.ends_with(&self.value),
+        }
+    }
+}
+
+/// Inclusive range of calendar dates, in any format SearchDocument::date accepts
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DateRange {
     pub from: String,
     pub to: String,
 }
 
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .or_else(|| DateTime::parse_from_rfc3339(value).ok().map(|dt| dt.date_naive()))
+        .or_else(|| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S").ok().map(|dt| dt.date()))
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SearchResult {
     pub documents: Vec<SearchDocument>,
     pub total: usize,
+    pub query_terms: Vec<String>,
     pub took_ms: f64,
     pub facets: Option<BTreeMap<String, BTreeMap<String, usize>>>,
 }
 
+/// Excerpt of `text` around the first token in `terms`, each matching token
+/// wrapped in <mark>. Tokens are split and stemmed as InvertedIndex::tokenize
+/// does, and the rest of the text is HTML-escaped.
+fn highlight_snippet(text: &str, terms: &AHashSet<String>, length: usize, index: &InvertedIndex) -> String {
+    let chars: Vec<char> = text.chars().collect();
+
+    // Char ranges of tokens that match
+    let mut marks = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        if !chars[start].is_alphanumeric() {
+            start += 1;
+            continue;
+        }
+        let mut end = start;
+        while end < chars.len() && chars[end].is_alphanumeric() {
+            end += 1;
+        }
+        let token: String = chars[start..end].iter().collect::<String>().to_lowercase();
+        if token.len() > 1 && index.normalize(&token).is_some_and(|term| terms.contains(&term)) {
+            marks.push((start, end));
+        }
+        start = end;
+    }
+
+    // Centre the window on the first match, or take the opening text
+    let length = length.max(1);
+    let window_start = match marks.first() {
+        Some(&(first, end)) => {
+            let slack = length.saturating_sub(end - first) / 2;
+            first.saturating_sub(slack).min(chars.len().saturating_sub(length))
+        }
+        None => 0,
+    };
+    let window_end = (window_start + length).min(chars.len());
+
+    let mut snippet = String::new();
+    if window_start > 0 {
+        snippet.push('…');
+    }
+    let mut pos = window_start;
+    for &(mark_start, mark_end) in marks.iter().filter(|(s, e)| *s >= window_start && *e <= window_end) {
+        push_escaped(&mut snippet, &chars[pos..mark_start]);
+        snippet.push_str("<mark>");
+        push_escaped(&mut snippet, &chars[mark_start..mark_end]);
+        snippet.push_str("</mark>");
+        pos = mark_end;
+    }
+    push_escaped(&mut snippet, &chars[pos..window_end]);
+    if window_end < chars.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+fn push_escaped(out: &mut String, chars: &[char]) {
+    for &c in chars {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+/// Breakdown of how one document's score for a query was computed
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ScoreExplanation {
+    pub doc_id: String,
+    /// Whether the document is in the query's results at all
+    pub matched: bool,
+    pub score: f64,
+    pub bm25_score: f64,
+    pub k1: f64,
+    pub b: f64,
+    pub doc_length: usize,
+    pub avg_doc_length: f64,
+    pub terms: Vec<TermScore>,
+    pub boosts: Vec<BoostMultiplier>,
+}
+
+/// A corrected query and how many documents it would match
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SpellingSuggestion {
+    pub query: String,
+    pub hits: usize,
+}
+
+/// One query term's share of a BM25 score
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TermScore {
+    pub term: String,
+    pub tf: usize,
+    pub doc_freq: usize,
+    pub idf: f64,
+    pub normalized_tf: f64,
+    pub contribution: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BoostMultiplier {
+    /// "title", or the category or tag that matched a boost_fields key
+    pub field: String,
+    pub multiplier: f64,
+}
+
+// Query terms paired with the documents each one hit
+type TermMatches = Vec<(String, AHashSet<usize>)>;
+
+// Output of SearchOptimizer::rank_documents
+struct Ranking {
+    scored_docs: Vec<(usize, f64)>,
+    term_matches: TermMatches,
+    // Terms fed to BM25, after phrase and prefix expansion
+    query_terms: Vec<String>,
+    // Index terms that actually matched, including fuzzy and prefix expansions
+    highlight_terms: AHashSet<String>,
+}
+
+// Boolean query tree. Adjacent clauses without an operator are OR'd, so
+// plain space-separated queries behave as before.
+enum QueryNode {
+    Term(String),
+    Phrase(Vec<String>),
+    Prefix(String),
+    And(Vec<QueryNode>),
+    Or(Vec<QueryNode>),
+    Not(Box<QueryNode>),
+}
+
+enum QueryToken {
+    Word(String),
+    Phrase(String),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+#[derive(Default)]
+struct QueryLeaves {
+    terms: Vec<String>,
+    phrases: Vec<Vec<String>>,
+    prefixes: Vec<String>,
+}
+
+// Parses `lond* AND (flat OR "garden flat") NOT rented`. Operators must be
+// upper case; NOT binds tightest, then AND, then OR. Malformed input is
+// parsed leniently: stray operators and parentheses are skipped. A trailing
+// `*` makes a prefix term, which needs at least two characters. Stop words
+// are dropped from terms and phrases, but never from prefixes, and prefixes
+// are matched against index terms unstemmed.
+struct ParsedQuery {
+    root: Option<QueryNode>,
+}
+
+impl ParsedQuery {
+    fn parse(query: &str, index: &InvertedIndex) -> Self {
+        let tokens = Self::lex(query);
+        let mut pos = 0;
+        let mut clauses = Vec::new();
+
+        while pos < tokens.len() {
+            if let Some(node) = Self::parse_or(&tokens, &mut pos, index) {
+                clauses.push(node);
+            }
+            // Skip an unmatched closing parenthesis
+            if matches!(tokens.get(pos), Some(QueryToken::Close)) {
+                pos += 1;
+            }
+        }
+
+        ParsedQuery { root: Self::combine(clauses, QueryNode::Or) }
+    }
+
+    fn lex(query: &str) -> Vec<QueryToken> {
+        let mut tokens = Vec::new();
+        let mut chars = query.chars().peekable();
+
+        while let Some(&c) = chars.peek() {
+            match c {
+                '(' => {
+                    chars.next();
+                    tokens.push(QueryToken::Open);
+                }
+                ')' => {
+                    chars.next();
+                    tokens.push(QueryToken::Close);
+                }
+                '"' => {
+                    chars.next();
+                    let text: String = chars.by_ref().take_while(|&c| c != '"').collect();
+                    tokens.push(QueryToken::Phrase(text));
+                }
+                c if c.is_whitespace() => {
+                    chars.next();
+                }
+                _ => {
+                    let mut word = String::new();
+                    while let Some(&c) = chars.peek() {
+                        if c.is_whitespace() || c == '(' || c == ')' || c == '"' {
+                            break;
+                        }
+                        word.push(c);
+                        chars.next();
+                    }
+                    tokens.push(match word.as_str() {
+                        "AND" => QueryToken::And,
+                        "OR" => QueryToken::Or,
+                        "NOT" => QueryToken::Not,
+                        _ => QueryToken::Word(word),
+                    });
+                }
+            }
+        }
+
+        tokens
+    }
+
+    fn parse_or(tokens: &[QueryToken], pos: &mut usize, index: &InvertedIndex) -> Option<QueryNode> {
+        let mut clauses = Vec::new();
+
+        while let Some(token) = tokens.get(*pos) {
+            match token {
+                QueryToken::Close => break,
+                QueryToken::Or => *pos += 1,
+                _ => clauses.extend(Self::parse_and(tokens, pos, index)),
+            }
+        }
+
+        Self::combine(clauses, QueryNode::Or)
+    }
+
+    fn parse_and(tokens: &[QueryToken], pos: &mut usize, index: &InvertedIndex) -> Option<QueryNode> {
+        let mut clauses: Vec<QueryNode> = Self::parse_not(tokens, pos, index).into_iter().collect();
+
+        while matches!(tokens.get(*pos), Some(QueryToken::And)) {
+            *pos += 1;
+            clauses.extend(Self::parse_not(tokens, pos, index));
+        }
+
+        Self::combine(clauses, QueryNode::And)
+    }
+
+    fn parse_not(tokens: &[QueryToken], pos: &mut usize, index: &InvertedIndex) -> Option<QueryNode> {
+        if matches!(tokens.get(*pos), Some(QueryToken::Not)) {
+            *pos += 1;
+            return Self::parse_not(tokens, pos, index).map(|node| QueryNode::Not(Box::new(node)));
+        }
+
+        Self::parse_atom(tokens, pos, index)
+    }
+
+    /// Always consumes at least one token
+    fn parse_atom(tokens: &[QueryToken], pos: &mut usize, index: &InvertedIndex) -> Option<QueryNode> {
+        let token = tokens.get(*pos)?;
+        *pos += 1;
+
+        match token {
+            QueryToken::Word(word) => {
+                let mut terms = InvertedIndex::split_terms(word);
+                let prefix = if word.ends_with('*') { terms.pop() } else { None };
+                let nodes = terms.iter()
+                    .filter_map(|term| index.normalize(term))
+                    .map(QueryNode::Term)
+                    .chain(prefix.map(QueryNode::Prefix))
+                    .collect();
+                Self::combine(nodes, QueryNode::Or)
+            }
+            QueryToken::Phrase(text) => {
+                let mut terms = index.tokenize(text);
+                match terms.len() {
+                    0 => None,
+                    1 => terms.pop().map(QueryNode::Term),
+                    _ => Some(QueryNode::Phrase(terms)),
+                }
+            }
+            QueryToken::Open => {
+                let node = Self::parse_or(tokens, pos, index);
+                if matches!(tokens.get(*pos), Some(QueryToken::Close)) {
+                    *pos += 1;
+                }
+                node
+            }
+            QueryToken::And | QueryToken::Or | QueryToken::Not | QueryToken::Close => None,
+        }
+    }
+
+    fn combine(mut clauses: Vec<QueryNode>, join: fn(Vec<QueryNode>) -> QueryNode) -> Option<QueryNode> {
+        match clauses.len() {
+            0 => None,
+            1 => clauses.pop(),
+            _ => Some(join(clauses)),
+        }
+    }
+
+    /// Leaves outside any NOT, i.e. the ones a match is credited with
+    fn positive_leaves(&self) -> QueryLeaves {
+        let mut leaves = QueryLeaves::default();
+        if let Some(root) = &self.root {
+            Self::collect_leaves(root, false, &mut leaves);
+        }
+        leaves
+    }
+
+    /// Every leaf in the query, negated or not
+    fn all_leaves(&self) -> QueryLeaves {
+        let mut leaves = QueryLeaves::default();
+        if let Some(root) = &self.root {
+            Self::collect_leaves(root, true, &mut leaves);
+        }
+        leaves
+    }
+
+    fn collect_leaves(node: &QueryNode, include_negated: bool, leaves: &mut QueryLeaves) {
+        match node {
+            QueryNode::Term(term) => leaves.terms.push(term.clone()),
+            QueryNode::Phrase(phrase) => leaves.phrases.push(phrase.clone()),
+            QueryNode::Prefix(prefix) => leaves.prefixes.push(prefix.clone()),
+            QueryNode::And(children) | QueryNode::Or(children) => {
+                for child in children {
+                    Self::collect_leaves(child, include_negated, leaves);
+                }
+            }
+            QueryNode::Not(child) => {
+                if include_negated {
+                    Self::collect_leaves(child, include_negated, leaves);
+                }
+            }
+        }
+    }
+
+    /// Documents satisfying the query, given each term's matches
+    fn evaluate(&self, index: &InvertedIndex, term_docs: &AHashMap<String, AHashSet<usize>>, total_docs: usize) -> AHashSet<usize> {
+        match &self.root {
+            Some(root) => Self::evaluate_node(root, index, term_docs, total_docs),
+            None => AHashSet::new(),
+        }
+    }
+
+    fn evaluate_node(node: &QueryNode, index: &InvertedIndex, term_docs: &AHashMap<String, AHashSet<usize>>, total_docs: usize) -> AHashSet<usize> {
+        match node {
+            QueryNode::Term(term) => term_docs.get(term).cloned().unwrap_or_default(),
+            QueryNode::Phrase(phrase) => index.phrase_documents(phrase),
+            QueryNode::Prefix(prefix) => term_docs.get(&format!("{}*", prefix)).cloned().unwrap_or_default(),
+            QueryNode::Or(children) => children.iter()
+                .flat_map(|child| Self::evaluate_node(child, index, term_docs, total_docs))
+                .collect(),
+            QueryNode::And(children) => {
+                let mut sets = children.iter().map(|child| Self::evaluate_node(child, index, term_docs, total_docs));
+                let first = sets.next().unwrap_or_default();
+                sets.fold(first, |acc, set| acc.intersection(&set).copied().collect())
+            }
+            QueryNode::Not(child) => {
+                let excluded = Self::evaluate_node(child, index, term_docs, total_docs);
+                (0..total_docs).filter(|doc_id| !excluded.contains(doc_id)).collect()
+            }
+        }
+    }
+}
+
+// BK-tree over index terms: finds every term within an edit distance
+// without comparing against the whole vocabulary. Terms are never removed,
+// so callers must check results still exist in the index.
+struct BkTree {
+    nodes: Vec<BkNode>,
+}
+
+struct BkNode {
+    term: String,
+    children: AHashMap<usize, usize>,  // edit distance -> node index
+}
+
+impl BkTree {
+    fn new() -> Self {
+        BkTree { nodes: Vec::new() }
+    }
+
+    fn insert(&mut self, term: &str) {
+        if self.nodes.is_empty() {
+            self.nodes.push(BkNode { term: term.to_string(), children: AHashMap::new() });
+            return;
+        }
+
+        let mut current = 0;
+        loop {
+            let distance = InvertedIndex::levenshtein_distance(term, &self.nodes[current].term);
+            if distance == 0 {
+                return;
+            }
+
+            match self.nodes[current].children.get(&distance) {
+                Some(&child) => current = child,
+                None => {
+                    let child = self.nodes.len();
+                    self.nodes.push(BkNode { term: term.to_string(), children: AHashMap::new() });
+                    self.nodes[current].children.insert(distance, child);
+                    return;
+                }
+            }
+        }
+    }
+
+    fn find_within(&self, term: &str, max_distance: usize) -> Vec<&str> {
+        let mut found = Vec::new();
+        if self.nodes.is_empty() {
+            return found;
+        }
+
+        let mut stack = vec![0];
+        while let Some(current) = stack.pop() {
+            let node = &self.nodes[current];
+            let distance = InvertedIndex::levenshtein_distance(term, &node.term);
+            if distance <= max_distance {
+                found.push(node.term.as_str());
+            }
+
+            // Triangle inequality: only children within max_distance of
+            // `distance` can hold matches
+            let low = distance.saturating_sub(max_distance);
+            let high = distance + max_distance;
+            stack.extend(
+                node.children.iter()
+                    .filter(|(d, _)| (low..=high).contains(*d))
+                    .map(|(_, &child)| child),
+            );
+        }
+
+        found
+    }
+}
+
 // Inverted index for fast text search
 struct InvertedIndex {
     term_documents: AHashMap<String, AHashSet<usize>>,
     document_terms: Vec<AHashSet<String>>,
     term_frequencies: AHashMap<String, AHashMap<usize, usize>>,
+    // Token offsets of each term per document, for phrase matching
+    term_positions: AHashMap<String, AHashMap<usize, Vec<usize>>>,
     document_lengths: Vec<usize>,
+    term_tree: BkTree,
+    stop_words: AHashSet<String>,
+    stemmer: Option<Stemmer>,
+    // Unstemmed words per document, so suggestions show real words
+    word_frequencies: AHashMap<String, AHashMap<usize, usize>>,
+    document_words: Vec<AHashSet<String>>,
 }
 
 impl InvertedIndex {
-    fn new() -> Self {
+    fn new(stop_words: AHashSet<String>, stemming: bool) -> Self {
         InvertedIndex {
             term_documents: AHashMap::new(),
             document_terms: Vec::new(),
             term_frequencies: AHashMap::new(),
+            term_positions: AHashMap::new(),
             document_lengths: Vec::new(),
+            term_tree: BkTree::new(),
+            stop_words,
+            stemmer: stemming.then(|| Stemmer::create(Algorithm::English)),
+            word_frequencies: AHashMap::new(),
+            document_words: Vec::new(),
         }
     }
 
     fn add_document(&mut self, doc_id: usize, text: &str) {
-        let terms = Self::tokenize(text);
+        let words: Vec<String> = Self::split_terms(text)
+            .into_iter()
+            .filter(|word| !self.stop_words.contains(word))
+            .collect();
+        let terms: Vec<String> = words.iter().map(|word| self.stem(word)).collect();
         let unique_terms: AHashSet<String> = terms.iter().cloned().collect();
 
         // Ensure vectors are large enough
         while self.document_terms.len() <= doc_id {
             self.document_terms.push(AHashSet::new());
+            self.document_words.push(AHashSet::new());
             self.document_lengths.push(0);
         }
 
         self.document_terms[doc_id] = unique_terms.clone();
         self.document_lengths[doc_id] = terms.len();
 
-        // Count term frequencies
+        for word in &words {
+            *self.word_frequencies.entry(word.clone()).or_default().entry(doc_id).or_insert(0) += 1;
+        }
+        self.document_words[doc_id] = words.into_iter().collect();
+
+        // Count term frequencies and record positions
         let mut term_freq: AHashMap<String, usize> = AHashMap::new();
-        for term in &terms {
+        for (position, term) in terms.iter().enumerate() {
             *term_freq.entry(term.clone()).or_insert(0) += 1;
+            self.term_positions
+                .entry(term.clone())
+                .or_default()
+                .entry(doc_id)
+                .or_default()
+                .push(position);
         }
 
         // Update inverted index
         for (term, freq) in term_freq {
+            if !self.term_documents.contains_key(&term) {
+                self.term_tree.insert(&term);
+            }
+
             self.term_documents
                 .entry(term.clone())
                 .or_insert_with(AHashSet::new)
@@ -107,7 +706,108 @@ impl InvertedIndex {
         }
     }
 
-    fn tokenize(text: &str) -> Vec<String> {
+    /// Drop a document's postings, leaving its slot empty
+    fn remove_postings(&mut self, doc_id: usize) {
+        let terms = std::mem::take(&mut self.document_terms[doc_id]);
+        self.document_lengths[doc_id] = 0;
+
+        for word in std::mem::take(&mut self.document_words[doc_id]) {
+            if let Some(freqs) = self.word_frequencies.get_mut(&word) {
+                freqs.remove(&doc_id);
+                if freqs.is_empty() {
+                    self.word_frequencies.remove(&word);
+                }
+            }
+        }
+
+        for term in &terms {
+            if let Some(docs) = self.term_documents.get_mut(term) {
+                docs.remove(&doc_id);
+                if docs.is_empty() {
+                    self.term_documents.remove(term);
+                }
+            }
+            if let Some(freqs) = self.term_frequencies.get_mut(term) {
+                freqs.remove(&doc_id);
+                if freqs.is_empty() {
+                    self.term_frequencies.remove(term);
+                }
+            }
+            if let Some(positions) = self.term_positions.get_mut(term) {
+                positions.remove(&doc_id);
+                if positions.is_empty() {
+                    self.term_positions.remove(term);
+                }
+            }
+        }
+    }
+
+    /// Remove a document, moving the last document into its slot so ids
+    /// stay dense (mirrors Vec::swap_remove on the document list)
+    fn remove_document(&mut self, doc_id: usize) {
+        self.remove_postings(doc_id);
+
+        let last = self.document_terms.len() - 1;
+        self.document_terms.swap_remove(doc_id);
+        self.document_words.swap_remove(doc_id);
+        self.document_lengths.swap_remove(doc_id);
+
+        if doc_id == last {
+            return;
+        }
+
+        for word in &self.document_words[doc_id] {
+            if let Some(freqs) = self.word_frequencies.get_mut(word) {
+                if let Some(freq) = freqs.remove(&last) {
+                    freqs.insert(doc_id, freq);
+                }
+            }
+        }
+
+        for term in &self.document_terms[doc_id] {
+            if let Some(docs) = self.term_documents.get_mut(term) {
+                docs.remove(&last);
+                docs.insert(doc_id);
+            }
+            if let Some(freqs) = self.term_frequencies.get_mut(term) {
+                if let Some(freq) = freqs.remove(&last) {
+                    freqs.insert(doc_id, freq);
+                }
+            }
+            if let Some(positions) = self.term_positions.get_mut(term) {
+                if let Some(list) = positions.remove(&last) {
+                    positions.insert(doc_id, list);
+                }
+            }
+        }
+    }
+
+    /// Index terms for `text`: stop words dropped, the rest stemmed
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        Self::split_terms(text)
+            .into_iter()
+            .filter_map(|word| self.normalize(&word))
+            .collect()
+    }
+
+    /// The index term for one lowercased word, or None for a stop word
+    fn normalize(&self, word: &str) -> Option<String> {
+        if self.stop_words.contains(word) {
+            None
+        } else {
+            Some(self.stem(word))
+        }
+    }
+
+    fn stem(&self, word: &str) -> String {
+        match &self.stemmer {
+            Some(stemmer) => stemmer.stem(word).into_owned(),
+            None => word.to_string(),
+        }
+    }
+
+    /// Lowercased words longer than one character, stop words included
+    fn split_terms(text: &str) -> Vec<String> {
         text.to_lowercase()
             .split(|c: char| !c.is_alphanumeric())
             .filter(|s| !s.is_empty() && s.len() > 1)
@@ -116,33 +816,118 @@ impl InvertedIndex {
     }
 
     fn search(&self, query: &str, fuzzy: bool, distance: usize) -> AHashSet<usize> {
-        let query_terms = Self::tokenize(query);
-        let mut matching_docs = AHashSet::new();
+        let query_terms = self.tokenize(query);
+
+        self.match_terms(&query_terms, fuzzy, distance)
+            .into_iter()
+            .flat_map(|(_, docs)| docs)
+            .collect()
+    }
+
+    /// Resolve each distinct query term to the documents it hits
+    fn match_terms(&self, query_terms: &[String], fuzzy: bool, distance: usize) -> TermMatches {
+        let mut seen = AHashSet::new();
+        let mut matches = Vec::new();
 
         for term in query_terms {
+            if !seen.insert(term.as_str()) {
+                continue;
+            }
+
+            let mut docs = AHashSet::new();
+
             // Exact match
-            if let Some(docs) = self.term_documents.get(&term) {
-                matching_docs.extend(docs);
+            if let Some(term_docs) = self.term_documents.get(term) {
+                docs.extend(term_docs);
             }
 
             // Fuzzy matching if enabled
             if fuzzy {
-                for index_term in self.term_documents.keys() {
-                    if Self::levenshtein_distance(&term, index_term) <= distance {
-                        if let Some(docs) = self.term_documents.get(index_term) {
-                            matching_docs.extend(docs);
-                        }
+                for index_term in self.term_tree.find_within(term, distance) {
+                    if let Some(term_docs) = self.term_documents.get(index_term) {
+                        docs.extend(term_docs);
                     }
                 }
             }
+
+            matches.push((term.clone(), docs));
+        }
+
+        matches
+    }
+
+    /// Nearest live index term by edit distance, preferring the more widely
+    /// used term and then the alphabetically first on ties
+    fn closest_term(&self, term: &str, max_distance: usize) -> Option<&str> {
+        self.term_tree.find_within(term, max_distance)
+            .into_iter()
+            .filter_map(|candidate| {
+                let docs = self.term_documents.get(candidate)?.len();
+                Some((Self::levenshtein_distance(term, candidate), Reverse(docs), candidate))
+            })
+            .min()
+            .map(|(_, _, candidate)| candidate)
+    }
+
+    /// Most frequent unstemmed word indexed under `term`
+    fn surface_word(&self, term: &str) -> Option<String> {
+        let mut counts: AHashMap<&str, usize> = AHashMap::new();
+        for &doc_id in self.term_documents.get(term)? {
+            for word in &self.document_words[doc_id] {
+                if self.stem(word) == term {
+                    counts.insert(word, self.word_frequencies[word].values().sum());
+                }
+            }
+        }
+
+        counts.into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(word, _)| word.to_string())
+    }
+
+    /// Up to `limit` index terms starting with `prefix`, most widely used first
+    fn expand_prefix(&self, prefix: &str, limit: usize) -> Vec<String> {
+        let mut candidates: Vec<(&String, usize)> = self.term_documents
+            .iter()
+            .filter(|(term, _)| term.starts_with(prefix))
+            .map(|(term, docs)| (term, docs.len()))
+            .collect();
+
+        candidates.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        candidates.into_iter().take(limit).map(|(term, _)| term.clone()).collect()
+    }
+
+    /// Documents containing the phrase as an adjacent run of tokens
+    fn phrase_documents(&self, phrase: &[String]) -> AHashSet<usize> {
+        let mut postings = Vec::with_capacity(phrase.len());
+        for term in phrase {
+            match self.term_positions.get(term) {
+                Some(docs) => postings.push(docs),
+                None => return AHashSet::new(),
+            }
         }
 
-        matching_docs
+        postings[0]
+            .iter()
+            .filter(|(doc_id, starts)| {
+                starts.iter().any(|&start| {
+                    postings.iter().enumerate().skip(1).all(|(offset, docs)| {
+                        docs.get(doc_id)
+                            .is_some_and(|positions| positions.binary_search(&(start + offset)).is_ok())
+                    })
+                })
+            })
+            .map(|(&doc_id, _)| doc_id)
+            .collect()
     }
 
     fn levenshtein_distance(s1: &str, s2: &str) -> usize {
-        let len1 = s1.len();
-        let len2 = s2.len();
+        // Counted in chars, not bytes, so the distance stays a true metric
+        // (which the BK-tree relies on) for non-ASCII terms
+        let s1_chars: Vec<char> = s1.chars().collect();
+        let s2_chars: Vec<char> = s2.chars().collect();
+        let len1 = s1_chars.len();
+        let len2 = s2_chars.len();
 
         if len1 == 0 {
             return len2;
@@ -151,9 +936,6 @@ impl InvertedIndex {
             return len1;
         }
 
-        let s1_chars: Vec<char> = s1.chars().collect();
-        let s2_chars: Vec<char> = s2.chars().collect();
-
         let mut prev_row: Vec<usize> = (0..=len2).collect();
         let mut curr_row = vec![0; len2 + 1];
 
@@ -178,11 +960,19 @@ impl InvertedIndex {
     }
 
     fn calculate_bm25_score(&self, doc_id: usize, query_terms: &[String], k1: f64, b: f64) -> f64 {
+        self.bm25_term_scores(doc_id, query_terms, k1, b)
+            .iter()
+            .map(|term| term.contribution)
+            .sum()
+    }
+
+    /// Per-term BM25 components for the query terms present in a document
+    fn bm25_term_scores(&self, doc_id: usize, query_terms: &[String], k1: f64, b: f64) -> Vec<TermScore> {
         let doc_length = self.document_lengths[doc_id] as f64;
-        let avg_doc_length = self.document_lengths.iter().sum::<usize>() as f64 / self.document_lengths.len() as f64;
+        let avg_doc_length = self.avg_document_length();
         let total_docs = self.document_lengths.len() as f64;
 
-        let mut score = 0.0;
+        let mut scores = Vec::new();
 
         for term in query_terms {
             if let Some(doc_freq_map) = self.term_frequencies.get(term) {
@@ -193,12 +983,23 @@ impl InvertedIndex {
                     let tf = term_freq as f64;
                     let normalized_tf = (tf * (k1 + 1.0)) / (tf + k1 * (1.0 - b + b * (doc_length / avg_doc_length)));
 
-                    score += idf * normalized_tf;
+                    scores.push(TermScore {
+                        term: term.clone(),
+                        tf: term_freq,
+                        doc_freq: doc_freq_map.len(),
+                        idf,
+                        normalized_tf,
+                        contribution: idf * normalized_tf,
+                    });
                 }
             }
         }
 
-        score
+        scores
+    }
+
+    fn avg_document_length(&self) -> f64 {
+        self.document_lengths.iter().sum::<usize>() as f64 / self.document_lengths.len() as f64
     }
 }
 
@@ -208,18 +1009,22 @@ pub struct SearchOptimizer {
     index: InvertedIndex,
     category_index: AHashMap<String, AHashSet<usize>>,
     tag_index: AHashMap<String, AHashSet<usize>>,
+    document_dates: Vec<Option<NaiveDate>>,
 }
 
 #[wasm_bindgen]
 impl SearchOptimizer {
+    /// Stemming is off unless `stemming` is true, so existing callers keep
+    /// exact-word matching and ranking
     #[wasm_bindgen(constructor)]
-    pub fn new() -> Self {
+    pub fn new(stemming: Option<bool>) -> Self {
         init_panic_hook();
         SearchOptimizer {
             documents: Vec::new(),
-            index: InvertedIndex::new(),
+            index: InvertedIndex::new(default_stop_words(), stemming.unwrap_or(false)),
             category_index: AHashMap::new(),
             tag_index: AHashMap::new(),
+            document_dates: Vec::new(),
         }
     }
 
@@ -232,27 +1037,130 @@ impl SearchOptimizer {
         let count = documents.len();
 
         for (idx, doc) in documents.iter().enumerate() {
-            // Index content
-            let combined_text = format!("{} {} {}", doc.title, doc.content, doc.tags.join(" "));
-            self.index.add_document(idx, &combined_text);
+            self.index_document(idx, doc);
+        }
+
+        self.document_dates = documents.iter().map(Self::document_date).collect();
+        self.documents = documents;
+        Ok(count)
+    }
+
+    /// Index one more document, returning the new document count
+    #[wasm_bindgen(js_name = addDocument)]
+    pub fn add_document(&mut self, doc_json: &str) -> Result<usize, JsValue> {
+        let doc: SearchDocument = serde_json::from_str(doc_json)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse document: {}", e)))?;
+
+        if self.find_document(&doc.id).is_some() {
+            return Err(JsValue::from_str(&format!("Document already exists: {}", doc.id)));
+        }
+
+        let idx = self.documents.len();
+        self.index_document(idx, &doc);
+        self.document_dates.push(Self::document_date(&doc));
+        self.documents.push(doc);
 
-            // Index category
-            self.category_index
-                .entry(doc.category.clone())
+        Ok(self.documents.len())
+    }
+
+    /// Replace a document in place, re-indexing its text, category and tags
+    #[wasm_bindgen(js_name = updateDocument)]
+    pub fn update_document(&mut self, id: &str, doc_json: &str) -> Result<(), JsValue> {
+        let doc: SearchDocument = serde_json::from_str(doc_json)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse document: {}", e)))?;
+
+        if doc.id != id {
+            return Err(JsValue::from_str(&format!("Document id {} does not match {}", doc.id, id)));
+        }
+
+        let idx = self.find_document(id)
+            .ok_or_else(|| JsValue::from_str(&format!("Document not found: {}", id)))?;
+
+        self.unindex_document(idx);
+        self.index_document(idx, &doc);
+        self.document_dates[idx] = Self::document_date(&doc);
+        self.documents[idx] = doc;
+
+        Ok(())
+    }
+
+    /// Remove a document, returning whether it existed
+    #[wasm_bindgen(js_name = removeDocument)]
+    pub fn remove_document(&mut self, id: &str) -> bool {
+        let Some(idx) = self.find_document(id) else {
+            return false;
+        };
+
+        self.unindex_document(idx);
+        self.index.remove_document(idx);
+
+        // The last document moves into the freed slot
+        let last = self.documents.len() - 1;
+        if idx != last {
+            let moved = &self.documents[last];
+            Self::move_posting(&mut self.category_index, &moved.category, last, idx);
+            for tag in &moved.tags {
+                Self::move_posting(&mut self.tag_index, tag, last, idx);
+            }
+        }
+
+        self.documents.swap_remove(idx);
+        self.document_dates.swap_remove(idx);
+        true
+    }
+
+    fn find_document(&self, id: &str) -> Option<usize> {
+        self.documents.iter().position(|doc| doc.id == id)
+    }
+
+    fn index_document(&mut self, idx: usize, doc: &SearchDocument) {
+        // Index content
+        self.index.add_document(idx, &Self::indexed_text(doc));
+
+        // Index category
+        self.category_index
+            .entry(doc.category.clone())
+            .or_insert_with(AHashSet::new)
+            .insert(idx);
+
+        // Index tags
+        for tag in &doc.tags {
+            self.tag_index
+                .entry(tag.clone())
                 .or_insert_with(AHashSet::new)
                 .insert(idx);
+        }
+    }
 
-            // Index tags
-            for tag in &doc.tags {
-                self.tag_index
-                    .entry(tag.clone())
-                    .or_insert_with(AHashSet::new)
-                    .insert(idx);
+    fn indexed_text(doc: &SearchDocument) -> String {
+        format!("{} {} {}", doc.title, doc.content, doc.tags.join(" "))
+    }
+
+    /// Remove a document's postings, category and tags, leaving its slot
+    fn unindex_document(&mut self, idx: usize) {
+        self.index.remove_postings(idx);
+
+        let doc = &self.documents[idx];
+        Self::remove_posting(&mut self.category_index, &doc.category, idx);
+        for tag in &doc.tags {
+            Self::remove_posting(&mut self.tag_index, tag, idx);
+        }
+    }
+
+    fn remove_posting(index: &mut AHashMap<String, AHashSet<usize>>, key: &str, idx: usize) {
+        if let Some(set) = index.get_mut(key) {
+            set.remove(&idx);
+            if set.is_empty() {
+                index.remove(key);
             }
         }
+    }
 
-        self.documents = documents;
-        Ok(count)
+    fn move_posting(index: &mut AHashMap<String, AHashSet<usize>>, key: &str, from: usize, to: usize) {
+        if let Some(set) = index.get_mut(key) {
+            set.remove(&from);
+            set.insert(to);
+        }
     }
 
     /// Perform optimized search
@@ -266,35 +1174,8 @@ impl SearchOptimizer {
         let query: SearchQuery = serde_json::from_str(query_json)
             .map_err(|e| JsValue::from_str(&format!("Failed to parse query: {}", e)))?;
 
-        // Get matching document IDs from text search
-        let fuzzy_distance = query.fuzzy_distance.unwrap_or(2);
-        let mut matching_ids = self.index.search(&query.query, query.fuzzy, fuzzy_distance);
-
-        // Apply filters
-        if let Some(filters) = &query.filters {
-            matching_ids = self.apply_filters(matching_ids, filters);
-        }
-
-        // Calculate scores
-        let query_terms = InvertedIndex::tokenize(&query.query);
-        let mut scored_docs: Vec<(usize, f64)> = matching_ids
-            .into_iter()
-            .map(|doc_id| {
-                let base_score = self.index.calculate_bm25_score(doc_id, &query_terms, 1.2, 0.75);
-                let boosted_score = self.apply_boost(doc_id, base_score, &query.boost_fields);
-                (doc_id, boosted_score)
-            })
-            .collect();
-
-        // Apply score threshold if specified
-        if let Some(filters) = &query.filters {
-            if let Some(threshold) = filters.score_threshold {
-                scored_docs.retain(|(_, score)| *score >= threshold);
-            }
-        }
-
-        // Sort by score (descending)
-        scored_docs.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        let Ranking { scored_docs, term_matches, highlight_terms, .. } = self.rank_documents(&query)?;
+        let snippet_length = query.snippet_length.unwrap_or(DEFAULT_SNIPPET_LENGTH);
 
         // Apply pagination
         let total = scored_docs.len();
@@ -306,11 +1187,20 @@ impl SearchOptimizer {
             .map(|(doc_id, score)| {
                 let mut doc = self.documents[*doc_id].clone();
                 doc.score = *score;
+                doc.matched_terms = term_matches
+                    .iter()
+                    .filter(|(_, docs)| docs.contains(doc_id))
+                    .map(|(term, _)| term.clone())
+                    .collect();
+                if query.highlight {
+                    doc.snippet = Some(highlight_snippet(&doc.content, &highlight_terms, snippet_length, &self.index));
+                }
                 doc
             })
             .collect();
 
-        // Calculate facets if needed
+        // Facets are only included when filters are given; like facets(),
+        // they count the full match set, not just the returned page
         let facets = if query.filters.is_some() {
             Some(self.calculate_facets(&scored_docs))
         } else {
@@ -325,6 +1215,7 @@ impl SearchOptimizer {
         let result = SearchResult {
             documents: result_docs,
             total,
+            query_terms: term_matches.into_iter().map(|(term, _)| term).collect(),
             took_ms: end - start,
             facets,
         };
@@ -333,7 +1224,221 @@ impl SearchOptimizer {
             .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
 
-    fn apply_filters(&self, mut doc_ids: AHashSet<usize>, filters: &SearchFilters) -> AHashSet<usize> {
+    /// Facet counts over every document matching a query, without fetching documents
+    #[wasm_bindgen(js_name = facets)]
+    pub fn facets(&self, query_json: &str) -> Result<String, JsValue> {
+        let query: SearchQuery = serde_json::from_str(query_json)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse query: {}", e)))?;
+
+        let ranking = self.rank_documents(&query)?;
+
+        serde_json::to_string(&self.calculate_facets(&ranking.scored_docs))
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Suggest a corrected query, replacing each word that matches nothing
+    /// with the closest index term. Null when every word already matches.
+    #[wasm_bindgen(js_name = didYouMean)]
+    pub fn did_you_mean(&self, query: &str) -> Result<String, JsValue> {
+        let mut corrected = false;
+        let words: Vec<String> = InvertedIndex::split_terms(query)
+            .into_iter()
+            .map(|word| {
+                // Stop words aren't indexed, so they are kept as typed
+                let Some(term) = self.index.normalize(&word) else {
+                    return word;
+                };
+                if self.index.term_documents.contains_key(&term) {
+                    return word;
+                }
+                match self.index.closest_term(&term, SPELLING_MAX_DISTANCE) {
+                    Some(replacement) => {
+                        corrected = true;
+                        self.index.surface_word(replacement).unwrap_or_else(|| replacement.to_string())
+                    }
+                    None => word,
+                }
+            })
+            .collect();
+
+        let suggestion = corrected.then(|| {
+            let query = words.join(" ");
+            let hits = self.index.search(&query, false, 0).len();
+            SpellingSuggestion { query, hits }
+        });
+
+        serde_json::to_string(&suggestion)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Per-term BM25 breakdown and boosts behind one document's score
+    #[wasm_bindgen(js_name = explain)]
+    pub fn explain(&self, query_json: &str, doc_id: &str) -> Result<String, JsValue> {
+        let query: SearchQuery = serde_json::from_str(query_json)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse query: {}", e)))?;
+
+        let idx = self.find_document(doc_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Document not found: {}", doc_id)))?;
+
+        let (k1, b) = bm25_params(query.k1, query.b)?;
+        let ranking = self.rank_documents(&query)?;
+
+        let terms = self.index.bm25_term_scores(idx, &ranking.query_terms, k1, b);
+        let boosts = self.boost_multipliers(idx, &ranking.query_terms, &query.boost_fields);
+        let bm25_score: f64 = terms.iter().map(|term| term.contribution).sum();
+        let score = boosts.iter().fold(bm25_score, |score, boost| score * boost.multiplier);
+
+        let explanation = ScoreExplanation {
+            doc_id: doc_id.to_string(),
+            matched: ranking.scored_docs.iter().any(|&(id, _)| id == idx),
+            score,
+            bm25_score,
+            k1,
+            b,
+            doc_length: self.index.document_lengths[idx],
+            avg_doc_length: self.index.avg_document_length(),
+            terms,
+            boosts,
+        };
+
+        serde_json::to_string(&explanation)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Match, filter and score documents, ordered by relevance
+    fn rank_documents(&self, query: &SearchQuery) -> Result<Ranking, JsValue> {
+        // Get matching document IDs from text search. Quoted phrases are
+        // matched by position rather than fuzzily; negated terms only
+        // exclude documents and are never credited or scored.
+        let (k1, b) = bm25_params(query.k1, query.b)?;
+        let parsed = ParsedQuery::parse(&query.query, &self.index);
+        let all_leaves = parsed.all_leaves();
+        let fuzzy_distance = query.fuzzy_distance.unwrap_or(2);
+        let mut term_docs: AHashMap<String, AHashSet<usize>> = self.index
+            .match_terms(&all_leaves.terms, query.fuzzy, fuzzy_distance)
+            .into_iter()
+            .collect();
+
+        // Prefix terms are keyed as "lond*", which no real term can clash with
+        let mut prefix_expansions: AHashMap<&str, Vec<String>> = AHashMap::new();
+        for prefix in &all_leaves.prefixes {
+            let expanded = self.index.expand_prefix(prefix, MAX_PREFIX_EXPANSIONS);
+            let docs = expanded.iter()
+                .filter_map(|term| self.index.term_documents.get(term))
+                .flatten()
+                .copied()
+                .collect();
+            term_docs.insert(format!("{}*", prefix), docs);
+            prefix_expansions.insert(prefix, expanded);
+        }
+
+        let mut matching_ids = parsed.evaluate(&self.index, &term_docs, self.documents.len());
+
+        let leaves = parsed.positive_leaves();
+        let mut seen = AHashSet::new();
+        let mut term_matches: TermMatches = leaves.terms.iter()
+            .cloned()
+            .chain(leaves.prefixes.iter().map(|prefix| format!("{}*", prefix)))
+            .filter(|key| seen.insert(key.clone()))
+            .map(|key| {
+                let docs = term_docs.get(&key).cloned().unwrap_or_default();
+                (key, docs)
+            })
+            .collect();
+        for phrase in &leaves.phrases {
+            term_matches.push((phrase.join(" "), self.index.phrase_documents(phrase)));
+        }
+        let query_terms: Vec<String> = leaves.terms.iter()
+            .chain(leaves.prefixes.iter().flat_map(|prefix| &prefix_expansions[prefix.as_str()]))
+            .chain(leaves.phrases.iter().flatten())
+            .cloned()
+            .collect();
+
+        // Require a minimum number of matched terms
+        if let Some(min_match) = query.min_should_match {
+            let required = min_match.min(term_matches.len());
+            matching_ids.retain(|doc_id| {
+                term_matches.iter().filter(|(_, docs)| docs.contains(doc_id)).count() >= required
+            });
+        }
+
+        // Apply filters
+        if let Some(filters) = &query.filters {
+            matching_ids = self.apply_filters(matching_ids, filters)?;
+        }
+
+        // Calculate scores
+        let mut scored_docs: Vec<(usize, f64)> = matching_ids
+            .into_iter()
+            .map(|doc_id| {
+                let base_score = self.index.calculate_bm25_score(doc_id, &query_terms, k1, b);
+                let boosted_score = self.apply_boost(doc_id, base_score, &query_terms, &query.boost_fields);
+                (doc_id, boosted_score)
+            })
+            .collect();
+
+        // Apply score threshold if specified
+        if let Some(filters) = &query.filters {
+            if let Some(threshold) = filters.score_threshold {
+                scored_docs.retain(|(_, score)| *score >= threshold);
+            }
+        }
+
+        // Sort by score (descending)
+        scored_docs.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+
+        // Collapse near-duplicates, keeping the highest-scoring copy
+        if let Some(threshold) = query.dedup_threshold {
+            scored_docs = self.dedup_documents(scored_docs, threshold);
+        }
+
+        if let Some(sort) = &query.sort {
+            self.sort_documents(&mut scored_docs, sort);
+        }
+
+        let mut highlight_terms: AHashSet<String> = query_terms.iter().cloned().collect();
+        if query.fuzzy {
+            for term in &leaves.terms {
+                highlight_terms.extend(self.index.term_tree.find_within(term, fuzzy_distance).into_iter().map(String::from));
+            }
+        }
+
+        Ok(Ranking { scored_docs, term_matches, query_terms, highlight_terms })
+    }
+
+    fn sort_documents(&self, scored_docs: &mut [(usize, f64)], sort: &SortSpec) {
+        let direction = |ordering: Ordering| if sort.ascending { ordering } else { ordering.reverse() };
+
+        match sort.field.as_str() {
+            "score" => scored_docs.sort_by(|a, b| direction(a.1.total_cmp(&b.1))),
+            "title" | "category" => {
+                let text = |doc_id: usize| {
+                    let doc = &self.documents[doc_id];
+                    if sort.field == "title" { doc.title.to_lowercase() } else { doc.category.to_lowercase() }
+                };
+                scored_docs.sort_by(|a, b| direction(text(a.0).cmp(&text(b.0))));
+            }
+            key => {
+                let value = |doc_id: usize| -> Option<f64> {
+                    self.documents[doc_id].metadata.as_ref()?.get(key)?.trim().parse().ok()
+                };
+                scored_docs.sort_by(|a, b| match (value(a.0), value(b.0)) {
+                    (Some(x), Some(y)) => direction(x.total_cmp(&y)),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                });
+            }
+        }
+    }
+
+    fn document_date(doc: &SearchDocument) -> Option<NaiveDate> {
+        doc.date.as_deref()
+            .or_else(|| doc.metadata.as_ref()?.get("date").map(String::as_str))
+            .and_then(parse_date)
+    }
+
+    fn apply_filters(&self, mut doc_ids: AHashSet<usize>, filters: &SearchFilters) -> Result<AHashSet<usize>, JsValue> {
         // Filter by categories
         if let Some(categories) = &filters.categories {
             let mut category_docs = AHashSet::new();
@@ -356,36 +1461,101 @@ impl SearchOptimizer {
             doc_ids = doc_ids.intersection(&tag_docs).cloned().collect();
         }
 
-        doc_ids
+        // Filter by metadata - document must match every rule or key/value pair
+        if let Some(metadata_filters) = &filters.metadata_filters {
+            doc_ids.retain(|&doc_id| {
+                let metadata = self.documents[doc_id].metadata.as_ref();
+                match metadata_filters {
+                    MetadataFilters::Rules(rules) => rules.iter().all(|rule| rule.matches(metadata)),
+                    MetadataFilters::Exact(pairs) => match metadata {
+                        Some(metadata) => pairs.iter().all(|(key, value)| metadata.get(key) == Some(value)),
+                        None => pairs.is_empty(),
+                    },
+                }
+            });
+        }
+
+        // Filter by date - documents without a parseable date are excluded
+        if let Some(range) = &filters.date_range {
+            let from = parse_date(&range.from)
+                .ok_or_else(|| JsValue::from_str(&format!("Invalid date_range.from: {}", range.from)))?;
+            let to = parse_date(&range.to)
+                .ok_or_else(|| JsValue::from_str(&format!("Invalid date_range.to: {}", range.to)))?;
+
+            doc_ids.retain(|&doc_id| {
+                matches!(self.document_dates[doc_id], Some(date) if date >= from && date <= to)
+            });
+        }
+
+        Ok(doc_ids)
     }
 
-    fn apply_boost(&self, doc_id: usize, base_score: f64, boost_fields: &Option<AHashMap<String, f64>>) -> f64 {
-        let mut score = base_score;
+    fn apply_boost(&self, doc_id: usize, base_score: f64, query_terms: &[String], boost_fields: &Option<AHashMap<String, f64>>) -> f64 {
+        self.boost_multipliers(doc_id, query_terms, boost_fields)
+            .iter()
+            .fold(base_score, |score, boost| score * boost.multiplier)
+    }
+
+    fn boost_multipliers(&self, doc_id: usize, query_terms: &[String], boost_fields: &Option<AHashMap<String, f64>>) -> Vec<BoostMultiplier> {
+        let mut multipliers = Vec::new();
 
         if let Some(boosts) = boost_fields {
             let doc = &self.documents[doc_id];
+            let mut push = |field: &str| {
+                if let Some(&multiplier) = boosts.get(field) {
+                    multipliers.push(BoostMultiplier { field: field.to_string(), multiplier });
+                }
+            };
 
-            // Boost by title matches
-            if let Some(title_boost) = boosts.get("title") {
-                if doc.title.to_lowercase().contains(&doc.title.to_lowercase()) {
-                    score *= title_boost;
+            // Boost when the title contains any query term
+            if boosts.contains_key("title") {
+                let title_terms = self.index.tokenize(&doc.title);
+                if query_terms.iter().any(|term| title_terms.contains(term)) {
+                    push("title");
                 }
             }
 
             // Boost by category
-            if let Some(category_boost) = boosts.get(&doc.category) {
-                score *= category_boost;
-            }
+            push(&doc.category);
 
             // Boost by tags
             for tag in &doc.tags {
-                if let Some(tag_boost) = boosts.get(tag) {
-                    score *= tag_boost;
-                }
+                push(tag);
             }
         }
 
-        score
+        multipliers
+    }
+
+    fn dedup_documents(&self, scored_docs: Vec<(usize, f64)>, threshold: f64) -> Vec<(usize, f64)> {
+        let mut kept: Vec<(usize, f64)> = Vec::new();
+        let mut kept_terms: Vec<AHashSet<String>> = Vec::new();
+
+        for (doc_id, score) in scored_docs {
+            let doc = &self.documents[doc_id];
+            let terms: AHashSet<String> = self.index.tokenize(&format!("{} {}", doc.title, doc.content))
+                .into_iter()
+                .collect();
+
+            if kept_terms.iter().any(|other| Self::jaccard_similarity(&terms, other) >= threshold) {
+                continue;
+            }
+
+            kept.push((doc_id, score));
+            kept_terms.push(terms);
+        }
+
+        kept
+    }
+
+    fn jaccard_similarity(a: &AHashSet<String>, b: &AHashSet<String>) -> f64 {
+        if a.is_empty() && b.is_empty() {
+            return 1.0;
+        }
+
+        let intersection = a.intersection(b).count();
+        let union = a.len() + b.len() - intersection;
+        intersection as f64 / union as f64
     }
 
     fn calculate_facets(&self, scored_docs: &[(usize, f64)]) -> BTreeMap<String, BTreeMap<String, usize>> {
@@ -415,30 +1585,15 @@ impl SearchOptimizer {
     #[wasm_bindgen(js_name = getSuggestions)]
     pub fn get_suggestions(&self, prefix: &str, limit: usize) -> Result<String, JsValue> {
         let prefix_lower = prefix.to_lowercase();
-        let mut suggestions = AHashSet::new();
-
-        // Collect terms starting with prefix
-        for term in self.index.term_documents.keys() {
-            if term.starts_with(&prefix_lower) {
-                suggestions.insert(term.clone());
-                if suggestions.len() >= limit * 2 {
-                    break;
-                }
-            }
-        }
 
-        // Sort by frequency (approximated by document count)
-        let mut sorted_suggestions: Vec<(String, usize)> = suggestions
-            .into_iter()
-            .map(|term| {
-                let doc_count = self.index.term_documents.get(&term)
-                    .map(|docs| docs.len())
-                    .unwrap_or(0);
-                (term, doc_count)
-            })
+        // Words starting with prefix, with total occurrences across documents
+        let mut sorted_suggestions: Vec<(String, usize)> = self.index.word_frequencies
+            .iter()
+            .filter(|(term, _)| term.starts_with(&prefix_lower))
+            .map(|(term, freqs)| (term.clone(), freqs.values().sum()))
             .collect();
 
-        sorted_suggestions.sort_by(|a, b| b.1.cmp(&a.1));
+        sorted_suggestions.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
 
         let result: Vec<String> = sorted_suggestions
             .into_iter()
@@ -450,22 +1605,23 @@ impl SearchOptimizer {
             .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
 
-    /// Batch scoring for multiple queries
+    /// Batch scoring for multiple queries, with optional BM25 k1 and b
     #[wasm_bindgen(js_name = batchScore)]
-    pub fn batch_score(&self, queries_json: &str) -> Result<String, JsValue> {
+    pub fn batch_score(&self, queries_json: &str, k1: Option<f64>, b: Option<f64>) -> Result<String, JsValue> {
         let queries: Vec<String> = serde_json::from_str(queries_json)
             .map_err(|e| JsValue::from_str(&format!("Failed to parse queries: {}", e)))?;
+        let (k1, b) = bm25_params(k1, b)?;
 
         let mut results = Vec::new();
 
         for query_str in queries {
-            let query_terms = InvertedIndex::tokenize(&query_str);
+            let query_terms = self.index.tokenize(&query_str);
             let matching_ids = self.index.search(&query_str, false, 0);
 
             let mut scored: Vec<(String, f64)> = matching_ids
                 .into_iter()
                 .map(|doc_id| {
-                    let score = self.index.calculate_bm25_score(doc_id, &query_terms, 1.2, 0.75);
+                    let score = self.index.calculate_bm25_score(doc_id, &query_terms, k1, b);
                     (self.documents[doc_id].id.clone(), score)
                 })
                 .collect();
@@ -507,12 +1663,168 @@ impl SearchOptimizer {
             .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
 
+    /// Replace the stop-word list (a JSON array of words) and reindex all
+    /// documents with it. An empty array disables stop-word removal.
+    #[wasm_bindgen(js_name = setStopWords)]
+    pub fn set_stop_words(&mut self, stop_words_json: &str) -> Result<(), JsValue> {
+        let words: Vec<String> = serde_json::from_str(stop_words_json)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse stop words: {}", e)))?;
+
+        let stop_words = words.iter().map(|word| word.to_lowercase()).collect();
+        self.index = InvertedIndex::new(stop_words, self.index.stemmer.is_some());
+        for (idx, doc) in self.documents.iter().enumerate() {
+            self.index.add_document(idx, &Self::indexed_text(doc));
+        }
+        Ok(())
+    }
+
     /// Clear all data
     #[wasm_bindgen(js_name = clear)]
     pub fn clear(&mut self) {
         self.documents.clear();
-        self.index = InvertedIndex::new();
+        let stemming = self.index.stemmer.is_some();
+        self.index = InvertedIndex::new(std::mem::take(&mut self.index.stop_words), stemming);
         self.category_index.clear();
         self.tag_index.clear();
+        self.document_dates.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(id: &str, title: &str, content: &str) -> String {
+        format!(
+            r#"{{"id":"{}","title":"{}","content":"{}","tags":[],"category":"listing","score":0,"metadata":null}}"#,
+            id, title, content
+        )
     }
-}
\ No newline at end of file
+
+    fn ranked_ids(optimizer: &SearchOptimizer, query_json: &str) -> Vec<String> {
+        let result: serde_json::Value = serde_json::from_str(&optimizer.search(query_json).unwrap()).unwrap();
+        result["documents"].as_array().unwrap()
+            .iter()
+            .map(|doc| doc["id"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn stemming_is_opt_in() {
+        let documents = format!("[{}]", document("houses", "Terraced houses", "three houses for sale"));
+        let query = r#"{"query":"house","limit":10,"offset":0,"fuzzy":false}"#;
+
+        let mut exact = SearchOptimizer::new(None);
+        exact.load_documents(&documents).unwrap();
+        assert!(ranked_ids(&exact, query).is_empty());
+
+        let mut stemmed = SearchOptimizer::new(Some(true));
+        stemmed.load_documents(&documents).unwrap();
+        assert_eq!(ranked_ids(&stemmed, query), ["houses"]);
+    }
+
+    #[test]
+    fn did_you_mean_keeps_stop_words() {
+        let mut optimizer = SearchOptimizer::new(None);
+        optimizer.load_documents(&format!("[{}]", document("flat", "Flat in Hampstead", "garden flat"))).unwrap();
+
+        let suggestion: serde_json::Value = serde_json::from_str(&optimizer.did_you_mean("flat in hampsted").unwrap()).unwrap();
+        assert_eq!(suggestion["query"], "flat in hampstead");
+        assert_eq!(suggestion["hits"], 1);
+    }
+
+    #[test]
+    fn title_boost_lifts_title_matches_over_body_matches() {
+        let mut optimizer = SearchOptimizer::new(None);
+        let documents = [
+            document("body", "Kilburn maisonette", "garden garden garden garden flat"),
+            document("title", "Garden flat", "bright and airy"),
+            document("other1", "Studio", "nothing relevant"),
+            document("other2", "Studio", "nothing relevant"),
+            document("other3", "Studio", "nothing relevant"),
+        ];
+        optimizer.load_documents(&format!("[{}]", documents.join(","))).unwrap();
+
+        let unboosted = r#"{"query":"garden","limit":10,"offset":0,"fuzzy":false}"#;
+        assert_eq!(ranked_ids(&optimizer, unboosted), ["body", "title"]);
+
+        let boosted = r#"{"query":"garden","limit":10,"offset":0,"fuzzy":false,"boost_fields":{"title":5.0}}"#;
+        assert_eq!(ranked_ids(&optimizer, boosted), ["title", "body"]);
+    }
+
+    /// Ids matching `query`, sorted so set comparisons ignore ranking
+    fn matching_ids(optimizer: &SearchOptimizer, query: &str) -> Vec<String> {
+        let query_json = serde_json::json!({ "query": query, "limit": 100, "offset": 0, "fuzzy": false });
+        let mut ids = ranked_ids(optimizer, &query_json.to_string());
+        ids.sort();
+        ids
+    }
+
+    fn boolean_fixture() -> SearchOptimizer {
+        let mut optimizer = SearchOptimizer::new(None);
+        let documents = [
+            document("a", "", "garden flat hampstead"),
+            document("b", "", "garden house kilburn"),
+            document("c", "", "flat kilburn"),
+            document("d", "", "studio"),
+        ];
+        optimizer.load_documents(&format!("[{}]", documents.join(","))).unwrap();
+        optimizer
+    }
+
+    #[test]
+    fn boolean_operators_bind_not_then_and_then_or() {
+        let optimizer = boolean_fixture();
+
+        assert_eq!(matching_ids(&optimizer, "flat OR garden AND kilburn"), ["a", "b", "c"]);
+        assert_eq!(matching_ids(&optimizer, "(flat OR garden) AND kilburn"), ["b", "c"]);
+        assert_eq!(matching_ids(&optimizer, "NOT flat AND kilburn"), ["b"]);
+        assert_eq!(matching_ids(&optimizer, "gard* AND NOT flat"), ["b"]);
+        // Without an operator, adjacent clauses are OR'd
+        assert_eq!(matching_ids(&optimizer, "hampstead studio"), ["a", "d"]);
+    }
+
+    #[test]
+    fn unbalanced_parentheses_are_parsed_leniently() {
+        let optimizer = boolean_fixture();
+
+        assert_eq!(matching_ids(&optimizer, "(garden AND flat"), ["a"]);
+        assert_eq!(matching_ids(&optimizer, "flat OR (garden AND (kilburn"), ["a", "b", "c"]);
+        assert_eq!(matching_ids(&optimizer, "garden) AND flat"), ["a", "b", "c"]);
+        assert!(matching_ids(&optimizer, "(()").is_empty());
+    }
+
+    #[test]
+    fn leading_not_excludes_from_every_document() {
+        let optimizer = boolean_fixture();
+
+        assert_eq!(matching_ids(&optimizer, "NOT kilburn"), ["a", "d"]);
+        assert_eq!(matching_ids(&optimizer, "NOT garden OR hampstead"), ["a", "c", "d"]);
+        assert!(matching_ids(&optimizer, "NOT").is_empty());
+    }
+
+    #[test]
+    fn phrases_track_documents_across_removal_and_update() {
+        let mut optimizer = SearchOptimizer::new(None);
+        let documents = [
+            document("p1", "", "garden flat near park"),
+            document("p2", "", "flat garden"),
+            document("p3", "", "large garden flat"),
+        ];
+        optimizer.load_documents(&format!("[{}]", documents.join(","))).unwrap();
+        let phrase = "\"garden flat\"";
+        assert_eq!(matching_ids(&optimizer, phrase), ["p1", "p3"]);
+
+        // p3 moves into p1's slot and keeps its positions
+        assert!(optimizer.remove_document("p1"));
+        assert_eq!(matching_ids(&optimizer, phrase), ["p3"]);
+        assert!(matching_ids(&optimizer, "\"flat near\"").is_empty());
+
+        optimizer.update_document("p2", &document("p2", "", "garden flat kilburn")).unwrap();
+        assert_eq!(matching_ids(&optimizer, phrase), ["p2", "p3"]);
+
+        assert!(optimizer.remove_document("p3"));
+        assert_eq!(matching_ids(&optimizer, phrase), ["p2"]);
+        assert!(!optimizer.remove_document("p3"));
+    }
+}
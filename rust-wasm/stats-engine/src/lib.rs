@@ -1,8 +1,17 @@
 use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
 use ahash::AHashMap;
+use std::borrow::Cow;
 use std::collections::BTreeMap;
 
+// Makes the MAD a consistent estimator of the normal standard deviation
+const MAD_SCALE: f64 = 1.4826;
+// Iglewicz and Hoaglin's cutoff for modified z-scores
+const ROBUST_Z_THRESHOLD: f64 = 3.5;
+const DEFAULT_MODE_DECIMALS: u32 = 2;
+// Beyond this, rounding no longer separates values an f64 can tell apart
+const MAX_MODE_DECIMALS: u32 = 15;
+
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
@@ -11,11 +20,82 @@ pub fn init_panic_hook() {
     console_error_panic_hook::set_once();
 }
 
+/// Category of a failure, so JS callers can branch on `code` instead of
+/// matching message text
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// A JSON argument couldn't be parsed
+    ParseError,
+    /// An argument is out of range or inconsistent with another
+    ValidationError,
+    /// There is no data to compute over
+    EmptyInput,
+    /// A named dataset isn't stored
+    NotFound,
+    /// The computation itself failed, e.g. a singular system
+    ComputationError,
+    SerializationError,
+}
+
+/// Error surfaced to JS as a `{code, message, field}` object
+#[derive(Serialize, Debug, Clone)]
+pub struct StatsError {
+    pub code: ErrorCode,
+    pub message: String,
+    /// The argument at fault, when a single one is
+    pub field: Option<String>,
+}
+
+impl StatsError {
+    fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        StatsError { code, message: message.into(), field: None }
+    }
+
+    fn parse(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::ParseError, message)
+    }
+
+    fn validation(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::ValidationError, message)
+    }
+
+    fn empty_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::EmptyInput, message)
+    }
+
+    fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    fn computation(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::ComputationError, message)
+    }
+
+    fn serialization(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::SerializationError, message)
+    }
+
+    fn with_field(mut self, field: &str) -> Self {
+        self.field = Some(field.to_string());
+        self
+    }
+}
+
+impl From<StatsError> for JsValue {
+    fn from(error: StatsError) -> Self {
+        error.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .unwrap_or_else(|_| JsValue::from_str(&error.message))
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct StatisticsResult {
     pub count: usize,
     pub sum: f64,
     pub mean: f64,
+    pub geometric_mean: Option<f64>,  // None unless every value is positive
+    pub harmonic_mean: Option<f64>,   // None unless every value is positive
     pub median: f64,
     pub mode: Vec<f64>,
     pub variance: f64,
@@ -28,6 +108,7 @@ pub struct StatisticsResult {
     pub outliers: Vec<f64>,
     pub skewness: f64,
     pub kurtosis: f64,
+    pub warnings: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -42,6 +123,7 @@ pub struct Quartiles {
 pub struct CorrelationResult {
     pub pearson: f64,
     pub spearman: f64,
+    pub kendall_tau: f64,  // tau-b, corrected for ties
     pub r_squared: f64,
 }
 
@@ -54,6 +136,48 @@ pub struct RegressionResult {
     pub predictions: Vec<f64>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WeightedStats {
+    pub weighted_mean: f64,
+    pub weighted_variance: f64,  // population (frequency) weights
+    pub weighted_std_dev: f64,
+    pub total_weight: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PolynomialRegressionResult {
+    pub coefficients: Vec<f64>,  // highest degree first
+    pub r_squared: f64,
+    pub predictions: Vec<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MultipleRegressionResult {
+    pub coefficients: Vec<f64>,  // intercept first, then one per feature
+    pub standard_errors: Vec<f64>,
+    pub r_squared: f64,
+    pub adjusted_r_squared: f64,
+    pub predictions: Vec<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Histogram {
+    pub edges: Vec<f64>,  // bin_count + 1 edges; the last bin includes its upper edge
+    pub counts: Vec<usize>,
+    pub out_of_range: usize,
+}
+
+// Every field but count serializes as null while the accumulator is empty
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OnlineSnapshot {
+    pub count: usize,
+    pub mean: f64,
+    pub variance: f64,  // population variance
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TimeSeriesStats {
     pub trend: Vec<f64>,
@@ -79,10 +203,58 @@ pub struct GroupStats {
     pub max: f64,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RobustStats {
+    pub median: f64,
+    pub mad: f64,  // scaled by 1.4826 to estimate the standard deviation
+    pub robust_z_scores: Vec<f64>,  // (x - median) / mad; empty when mad == 0
+    pub anomalies: Vec<usize>,  // indices with |robust z| > 3.5
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BootstrapResult {
+    pub mean: f64,  // point estimate from the original sample
+    pub lower: f64,
+    pub upper: f64,
+    pub confidence: f64,
+    pub iterations: usize,
+}
+
+/// Percentile interpolation, matching NumPy's methods of the same names
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PercentileMethod {
+    Linear,
+    Lower,
+    Higher,
+    Nearest,
+    Midpoint,
+}
+
+// SplitMix64: tiny, fast and fully determined by its seed, which is all
+// bootstrap resampling needs
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform index in 0..n
+    fn next_index(&mut self, n: usize) -> usize {
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+}
+
 #[wasm_bindgen]
 pub struct StatsEngine {
     data_cache: AHashMap<String, Vec<f64>>,
     results_cache: AHashMap<String, String>,
+    mode_decimals: u32,
 }
 
 #[wasm_bindgen]
@@ -93,24 +265,46 @@ impl StatsEngine {
         StatsEngine {
             data_cache: AHashMap::new(),
             results_cache: AHashMap::new(),
+            mode_decimals: DEFAULT_MODE_DECIMALS,
         }
     }
 
-    /// Calculate comprehensive statistics for a dataset
+    /// Set the default number of decimal places calculateStats rounds to
+    /// when grouping values for the mode (initially 2, max 15). Integer-valued
+    /// data is always counted exactly.
+    #[wasm_bindgen(js_name = setModePrecision)]
+    pub fn set_mode_precision(&mut self, decimals: u32) {
+        self.mode_decimals = decimals.min(MAX_MODE_DECIMALS);
+    }
+
+    /// Calculate comprehensive statistics for a dataset. `mode_decimals`
+    /// overrides setModePrecision for this call.
     #[wasm_bindgen(js_name = calculateStats)]
-    pub fn calculate_stats(&mut self, data_json: &str) -> Result<String, JsValue> {
-        let mut data: Vec<f64> = serde_json::from_str(data_json)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse data: {}", e)))?;
+    pub fn calculate_stats(&mut self, data_json: &str, dataset: Option<String>, mode_decimals: Option<u32>) -> Result<String, JsValue> {
+        let mut data = self.load_data(data_json, dataset.as_deref())?.into_owned();
 
         if data.is_empty() {
-            return Err(JsValue::from_str("Data array is empty"));
+            return Err(StatsError::empty_input("Data array is empty").into());
         }
 
-        data.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        self.ensure_finite(&data, "Data", "data_json")?;
+        data.sort_by(|a, b| a.total_cmp(b));
 
         let count = data.len();
         let sum: f64 = data.iter().sum();
         let mean = sum / count as f64;
+        let mut warnings = Vec::new();
+
+        // Geometric and harmonic means are only defined for positive data.
+        // The data is sorted, so checking the minimum is enough.
+        let (geometric_mean, harmonic_mean) = if data[0] > 0.0 {
+            let log_mean = data.iter().map(|x| x.ln()).sum::<f64>() / count as f64;
+            let reciprocal_sum: f64 = data.iter().map(|x| 1.0 / x).sum();
+            (Some(log_mean.exp()), Some(count as f64 / reciprocal_sum))
+        } else {
+            warnings.push("geometric_mean and harmonic_mean require all values > 0".to_string());
+            (None, None)
+        };
 
         // Calculate median
         let median = if count % 2 == 0 {
@@ -120,7 +314,8 @@ impl StatsEngine {
         };
 
         // Calculate mode
-        let mode = self.calculate_mode(&data);
+        let mode_decimals = mode_decimals.map_or(self.mode_decimals, |d| d.min(MAX_MODE_DECIMALS));
+        let mode = self.calculate_mode(&data, mode_decimals);
 
         // Calculate variance and standard deviation
         let variance = data.iter()
@@ -159,6 +354,8 @@ impl StatsEngine {
             count,
             sum,
             mean,
+            geometric_mean,
+            harmonic_mean,
             median,
             mode,
             variance,
@@ -171,10 +368,11 @@ impl StatsEngine {
             outliers,
             skewness,
             kurtosis,
+            warnings,
         };
 
         let json = serde_json::to_string(&result)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))?;
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)))?;
 
         self.results_cache.insert("last_stats".to_string(), json.clone());
         Ok(json)
@@ -191,26 +389,218 @@ impl StatsEngine {
 
     /// Calculate a specific percentile
     fn percentile(&self, sorted_data: &[f64], percentile: f64) -> f64 {
+        self.percentile_with(sorted_data, percentile, PercentileMethod::Linear)
+    }
+
+    /// Calculate a percentile (0-100) of sorted data with the given method
+    fn percentile_with(&self, sorted_data: &[f64], percentile: f64, method: PercentileMethod) -> f64 {
         let index = (sorted_data.len() as f64 - 1.0) * (percentile / 100.0);
         let lower = index.floor() as usize;
         let upper = index.ceil() as usize;
 
         if lower == upper {
-            sorted_data[lower]
+            return sorted_data[lower];
+        }
+
+        match method {
+            PercentileMethod::Linear => {
+                let weight = index - lower as f64;
+                sorted_data[lower] * (1.0 - weight) + sorted_data[upper] * weight
+            }
+            PercentileMethod::Lower => sorted_data[lower],
+            PercentileMethod::Higher => sorted_data[upper],
+            // Ties round to the even index, as NumPy does
+            PercentileMethod::Nearest => sorted_data[index.round_ties_even() as usize],
+            PercentileMethod::Midpoint => (sorted_data[lower] + sorted_data[upper]) / 2.0,
+        }
+    }
+
+    /// Calculate several percentiles (0-100) at once. `method` is one of
+    /// "linear" (default), "lower", "higher", "nearest" or "midpoint".
+    /// Returns values in the order of `ps_json`.
+    #[wasm_bindgen(js_name = percentiles)]
+    pub fn percentiles(&self, data_json: &str, ps_json: &str, method: Option<String>, dataset: Option<String>) -> Result<String, JsValue> {
+        let mut data = self.load_data(data_json, dataset.as_deref())?.into_owned();
+        let ps: Vec<f64> = serde_json::from_str(ps_json)
+            .map_err(|e| StatsError::parse(format!("Failed to parse percentiles: {}", e)).with_field("ps_json"))?;
+
+        let method = match method.as_deref() {
+            None => PercentileMethod::Linear,
+            Some(name) => serde_json::from_value(serde_json::Value::String(name.to_string()))
+                .map_err(|_| StatsError::validation(format!("Unknown percentile method: {}", name)).with_field("method"))?,
+        };
+
+        if data.is_empty() {
+            return Err(StatsError::empty_input("Data array is empty").into());
+        }
+
+        if let Some(p) = ps.iter().find(|p| !(0.0..=100.0).contains(*p)) {
+            return Err(StatsError::validation(format!("Percentile must be between 0 and 100, got {}", p)).with_field("ps_json").into());
+        }
+
+        data.sort_by(|a, b| a.total_cmp(b));
+
+        let values: Vec<f64> = ps.iter()
+            .map(|&p| self.percentile_with(&data, p, method))
+            .collect();
+
+        serde_json::to_string(&values)
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
+    /// Median and MAD-based robust z-scores, which outliers can't inflate
+    /// the way they inflate the standard deviation
+    #[wasm_bindgen(js_name = robustStats)]
+    pub fn robust_stats(&self, data_json: &str, dataset: Option<String>) -> Result<String, JsValue> {
+        let data = self.load_data(data_json, dataset.as_deref())?;
+
+        if data.is_empty() {
+            return Err(StatsError::empty_input("Data array is empty").into());
+        }
+
+        let (median, mad) = self.median_absolute_deviation(&data);
+
+        let robust_z_scores: Vec<f64> = if mad == 0.0 {
+            Vec::new()
         } else {
-            let weight = index - lower as f64;
-            sorted_data[lower] * (1.0 - weight) + sorted_data[upper] * weight
+            data.iter().map(|x| (x - median) / mad).collect()
+        };
+
+        let anomalies = robust_z_scores.iter()
+            .enumerate()
+            .filter(|(_, z)| z.abs() > ROBUST_Z_THRESHOLD)
+            .map(|(i, _)| i)
+            .collect();
+
+        let result = RobustStats {
+            median,
+            mad,
+            robust_z_scores,
+            anomalies,
+        };
+
+        serde_json::to_string(&result)
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
+    /// Median and MAD (scaled to be consistent with the standard deviation
+    /// for normal data) of non-empty data
+    fn median_absolute_deviation(&self, data: &[f64]) -> (f64, f64) {
+        let mut sorted = data.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let median = self.percentile(&sorted, 50.0);
+
+        let mut deviations: Vec<f64> = data.iter().map(|x| (x - median).abs()).collect();
+        deviations.sort_by(|a, b| a.total_cmp(b));
+        let mad = self.percentile(&deviations, 50.0) * MAD_SCALE;
+
+        (median, mad)
+    }
+
+    /// Clamp values below the `lower_pct` percentile and above the
+    /// (100 - `upper_pct`) percentile to those percentiles, keeping order.
+    /// `upper_pct` is the size of the upper tail, not a percentile, so
+    /// (10, 10) clamps the bottom and top 10%. Both tail sizes must be in
+    /// [0, 50), which keeps the lower cut below the upper one.
+    #[wasm_bindgen(js_name = winsorize)]
+    pub fn winsorize(&self, data_json: &str, lower_pct: f64, upper_pct: f64, dataset: Option<String>) -> Result<String, JsValue> {
+        let data = self.load_data(data_json, dataset.as_deref())?;
+
+        let (low, high) = self.tail_bounds(&data, lower_pct, upper_pct)?;
+        let clamped: Vec<f64> = data.iter().map(|x| x.clamp(low, high)).collect();
+
+        serde_json::to_string(&clamped)
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
+    /// Drop values below the `lower_pct` percentile and above the
+    /// (100 - `upper_pct`) percentile, keeping order. As in winsorize,
+    /// `upper_pct` is the size of the upper tail, and both tail sizes must
+    /// be in [0, 50).
+    #[wasm_bindgen(js_name = trim)]
+    pub fn trim(&self, data_json: &str, lower_pct: f64, upper_pct: f64, dataset: Option<String>) -> Result<String, JsValue> {
+        let data = self.load_data(data_json, dataset.as_deref())?;
+
+        let (low, high) = self.tail_bounds(&data, lower_pct, upper_pct)?;
+        let trimmed: Vec<f64> = data.iter().copied().filter(|x| (low..=high).contains(x)).collect();
+
+        serde_json::to_string(&trimmed)
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
+    /// Percentile bounds that cut `lower_pct` and `upper_pct` percent tails
+    fn tail_bounds(&self, data: &[f64], lower_pct: f64, upper_pct: f64) -> Result<(f64, f64), StatsError> {
+        if !(0.0..50.0).contains(&lower_pct) || !(0.0..50.0).contains(&upper_pct) {
+            return Err(StatsError::validation("Tail percentages must be in [0, 50)"));
+        }
+
+        if data.is_empty() {
+            return Err(StatsError::empty_input("Data array is empty"));
+        }
+
+        let mut sorted = data.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+
+        Ok((
+            self.percentile(&sorted, lower_pct),
+            self.percentile(&sorted, 100.0 - upper_pct),
+        ))
+    }
+
+    /// Percentile bootstrap confidence interval for the mean. The same seed
+    /// always gives the same interval.
+    #[wasm_bindgen(js_name = bootstrapMeanCI)]
+    pub fn bootstrap_mean_ci(&self, data_json: &str, iterations: usize, confidence: f64, seed: u32, dataset: Option<String>) -> Result<String, JsValue> {
+        let data = self.load_data(data_json, dataset.as_deref())?;
+
+        if data.is_empty() {
+            return Err(StatsError::empty_input("Data array is empty").into());
+        }
+
+        if iterations == 0 {
+            return Err(StatsError::validation("Iterations must be at least 1").with_field("iterations").into());
         }
+
+        if !(confidence > 0.0 && confidence < 1.0) {
+            return Err(StatsError::validation("Confidence must be between 0 and 1 (exclusive)").with_field("confidence").into());
+        }
+
+        let n = data.len();
+        let mut rng = SplitMix64(seed as u64);
+        let mut means: Vec<f64> = (0..iterations)
+            .map(|_| (0..n).map(|_| data[rng.next_index(n)]).sum::<f64>() / n as f64)
+            .collect();
+        means.sort_by(|a, b| a.total_cmp(b));
+
+        let tail = (1.0 - confidence) / 2.0 * 100.0;
+        let result = BootstrapResult {
+            mean: data.iter().sum::<f64>() / n as f64,
+            lower: self.percentile(&means, tail),
+            upper: self.percentile(&means, 100.0 - tail),
+            confidence,
+            iterations,
+        };
+
+        serde_json::to_string(&result)
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
     }
 
     /// Calculate mode(s)
-    fn calculate_mode(&self, data: &[f64]) -> Vec<f64> {
-        let mut frequency: AHashMap<i64, usize> = AHashMap::new();
+    fn calculate_mode(&self, data: &[f64], decimals: u32) -> Vec<f64> {
+        let mut frequency: AHashMap<u64, usize> = AHashMap::new();
+
+        // Integer data is counted as-is; anything else is bucketed at
+        // `decimals` places. Buckets are keyed on the rounded value's bits,
+        // which unlike an i64 of value * 10^decimals cannot saturate.
+        let integral = data.iter().all(|v| v.fract() == 0.0);
+        let scale = if integral { 1.0 } else { 10_f64.powi(decimals as i32) };
 
         for &value in data {
-            // Convert to integer for grouping (multiply by 100 to preserve 2 decimal places)
-            let key = (value * 100.0).round() as i64;
-            *frequency.entry(key).or_insert(0) += 1;
+            let rounded = (value * scale).round() / scale;
+            // Values too large to scale have no digits left to round off;
+            // adding 0.0 merges -0.0 into 0.0
+            let bucket = if rounded.is_finite() { rounded } else { value } + 0.0;
+            *frequency.entry(bucket.to_bits()).or_insert(0) += 1;
         }
 
         let max_frequency = *frequency.values().max().unwrap_or(&0);
@@ -221,7 +611,7 @@ impl StatsEngine {
         frequency
             .into_iter()
             .filter(|(_, count)| *count == max_frequency)
-            .map(|(value, _)| value as f64 / 100.0)
+            .map(|(bits, _)| f64::from_bits(bits))
             .collect()
     }
 
@@ -256,22 +646,190 @@ impl StatsEngine {
         kurtosis - adjustment
     }
 
+    /// Calculate weighted mean, variance and standard deviation
+    #[wasm_bindgen(js_name = weightedStats)]
+    pub fn weighted_stats(&self, values_json: &str, weights_json: &str, dataset: Option<String>) -> Result<String, JsValue> {
+        let values = match dataset.as_deref() {
+            Some(name) => self.load_data(values_json, Some(name))?,
+            None => Cow::Owned(serde_json::from_str(values_json)
+                .map_err(|e| StatsError::parse(format!("Failed to parse values: {}", e)).with_field("values_json"))?),
+        };
+        let weights: Vec<f64> = serde_json::from_str(weights_json)
+            .map_err(|e| StatsError::parse(format!("Failed to parse weights: {}", e)).with_field("weights_json"))?;
+
+        if values.len() != weights.len() {
+            return Err(StatsError::validation(format!(
+                "Values and weights must have the same length ({} vs {})",
+                values.len(), weights.len()
+            )).with_field("weights_json").into());
+        }
+
+        if let Some(i) = weights.iter().position(|w| w.is_nan() || *w < 0.0) {
+            return Err(StatsError::validation(format!(
+                "Weights must be non-negative, got {} at index {}", weights[i], i
+            )).with_field("weights_json").into());
+        }
+
+        let total_weight: f64 = weights.iter().sum();
+        if total_weight <= 0.0 || !total_weight.is_finite() {
+            return Err(StatsError::validation("Weights must sum to a positive finite value").with_field("weights_json").into());
+        }
+
+        let weighted_mean = values.iter()
+            .zip(&weights)
+            .map(|(x, w)| x * w)
+            .sum::<f64>() / total_weight;
+
+        let weighted_variance = values.iter()
+            .zip(&weights)
+            .map(|(x, w)| w * (x - weighted_mean).powi(2))
+            .sum::<f64>() / total_weight;
+
+        let result = WeightedStats {
+            weighted_mean,
+            weighted_variance,
+            weighted_std_dev: weighted_variance.sqrt(),
+            total_weight,
+        };
+
+        serde_json::to_string(&result)
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
     /// Calculate correlation between two datasets
     #[wasm_bindgen(js_name = calculateCorrelation)]
     pub fn calculate_correlation(&self, x_json: &str, y_json: &str) -> Result<String, JsValue> {
         let x: Vec<f64> = serde_json::from_str(x_json)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse x data: {}", e)))?;
+            .map_err(|e| StatsError::parse(format!("Failed to parse x data: {}", e)).with_field("x_json"))?;
         let y: Vec<f64> = serde_json::from_str(y_json)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse y data: {}", e)))?;
+            .map_err(|e| StatsError::parse(format!("Failed to parse y data: {}", e)).with_field("y_json"))?;
+
+        self.ensure_finite(&x, "x data", "x_json")?;
+        self.ensure_finite(&y, "y data", "y_json")?;
 
         if x.len() != y.len() {
-            return Err(JsValue::from_str("Data arrays must have the same length"));
+            return Err(StatsError::validation("Data arrays must have the same length").with_field("y_json").into());
         }
 
         if x.is_empty() {
-            return Err(JsValue::from_str("Data arrays are empty"));
+            return Err(StatsError::empty_input("Data arrays are empty").into());
+        }
+
+        let pearson = self.calculate_pearson(&x, &y);
+
+        // Spearman rank correlation
+        let spearman = self.calculate_spearman(&x, &y);
+
+        let result = CorrelationResult {
+            pearson,
+            spearman,
+            kendall_tau: self.calculate_kendall_tau(&x, &y),
+            r_squared: pearson * pearson,
+        };
+
+        serde_json::to_string(&result)
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
+    /// Calculate a Pearson correlation matrix for named columns of equal length
+    #[wasm_bindgen(js_name = correlationMatrix)]
+    pub fn correlation_matrix(&self, columns_json: &str) -> Result<String, JsValue> {
+        let columns: BTreeMap<String, Vec<f64>> = serde_json::from_str(columns_json)
+            .map_err(|e| StatsError::parse(format!("Failed to parse columns: {}", e)).with_field("columns_json"))?;
+
+        self.validate_columns(&columns, 1)?;
+
+        let mut matrix: BTreeMap<&str, BTreeMap<&str, f64>> = BTreeMap::new();
+        for (name_a, a) in &columns {
+            let row = matrix.entry(name_a).or_default();
+            for (name_b, b) in &columns {
+                let value = if name_a == name_b { 1.0 } else { self.calculate_pearson(a, b) };
+                row.insert(name_b, value);
+            }
+        }
+
+        serde_json::to_string(&matrix)
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
+    /// Calculate covariance of two series; `sample` divides by n - 1
+    /// instead of n
+    #[wasm_bindgen(js_name = covariance)]
+    pub fn covariance(&self, x_json: &str, y_json: &str, sample: bool) -> Result<f64, JsValue> {
+        let x: Vec<f64> = serde_json::from_str(x_json)
+            .map_err(|e| StatsError::parse(format!("Failed to parse x data: {}", e)).with_field("x_json"))?;
+        let y: Vec<f64> = serde_json::from_str(y_json)
+            .map_err(|e| StatsError::parse(format!("Failed to parse y data: {}", e)).with_field("y_json"))?;
+
+        if x.len() != y.len() {
+            return Err(StatsError::validation("Data arrays must have the same length").with_field("y_json").into());
+        }
+
+        if x.len() < 2 {
+            return Err(StatsError::validation("Covariance needs at least two observations").into());
+        }
+
+        Ok(self.calculate_covariance(&x, &y, sample))
+    }
+
+    /// Calculate a covariance matrix for named columns of equal length
+    #[wasm_bindgen(js_name = covarianceMatrix)]
+    pub fn covariance_matrix(&self, columns_json: &str, sample: bool) -> Result<String, JsValue> {
+        let columns: BTreeMap<String, Vec<f64>> = serde_json::from_str(columns_json)
+            .map_err(|e| StatsError::parse(format!("Failed to parse columns: {}", e)).with_field("columns_json"))?;
+
+        self.validate_columns(&columns, 2)?;
+
+        let mut matrix: BTreeMap<&str, BTreeMap<&str, f64>> = BTreeMap::new();
+        for (name_a, a) in &columns {
+            let row = matrix.entry(name_a).or_default();
+            for (name_b, b) in &columns {
+                row.insert(name_b, self.calculate_covariance(a, b, sample));
+            }
         }
 
+        serde_json::to_string(&matrix)
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
+    /// Covariance of equal-length series with at least two values
+    fn calculate_covariance(&self, x: &[f64], y: &[f64], sample: bool) -> f64 {
+        let n = x.len() as f64;
+        let mean_x = x.iter().sum::<f64>() / n;
+        let mean_y = y.iter().sum::<f64>() / n;
+        let sum: f64 = x.iter()
+            .zip(y)
+            .map(|(xi, yi)| (xi - mean_x) * (yi - mean_y))
+            .sum();
+
+        sum / if sample { n - 1.0 } else { n }
+    }
+
+    /// Check named columns are non-empty, share one length and hold at
+    /// least `min_len` observations
+    fn validate_columns(&self, columns: &BTreeMap<String, Vec<f64>>, min_len: usize) -> Result<(), StatsError> {
+        let mut lengths = columns.iter().map(|(name, values)| (name, values.len()));
+        let (first_name, len) = lengths.next()
+            .ok_or_else(|| StatsError::empty_input("No columns provided").with_field("columns_json"))?;
+
+        if let Some((name, other)) = lengths.find(|(_, l)| *l != len) {
+            return Err(StatsError::validation(format!(
+                "Column '{}' has {} values but column '{}' has {}",
+                name, other, first_name, len
+            )).with_field("columns_json"));
+        }
+
+        if len < min_len {
+            return Err(StatsError::validation(format!(
+                "Columns need at least {} values, got {}", min_len, len
+            )).with_field("columns_json"));
+        }
+
+        Ok(())
+    }
+
+    /// Calculate Pearson correlation; 0.0 when either series has no variance
+    fn calculate_pearson(&self, x: &[f64], y: &[f64]) -> f64 {
         let n = x.len() as f64;
         let sum_x: f64 = x.iter().sum();
         let sum_y: f64 = y.iter().sum();
@@ -279,27 +837,14 @@ impl StatsEngine {
         let sum_x2: f64 = x.iter().map(|xi| xi * xi).sum();
         let sum_y2: f64 = y.iter().map(|yi| yi * yi).sum();
 
-        // Pearson correlation coefficient
         let numerator = n * sum_xy - sum_x * sum_y;
         let denominator = ((n * sum_x2 - sum_x * sum_x) * (n * sum_y2 - sum_y * sum_y)).sqrt();
 
-        let pearson = if denominator == 0.0 {
+        if denominator == 0.0 {
             0.0
         } else {
             numerator / denominator
-        };
-
-        // Spearman rank correlation
-        let spearman = self.calculate_spearman(&x, &y);
-
-        let result = CorrelationResult {
-            pearson,
-            spearman,
-            r_squared: pearson * pearson,
-        };
-
-        serde_json::to_string(&result)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+        }
     }
 
     /// Calculate Spearman rank correlation
@@ -316,6 +861,47 @@ impl StatsEngine {
         1.0 - (6.0 * sum_diff_squared) / (n * (n * n - 1.0))
     }
 
+    /// Calculate Kendall's tau-b over all pairs, O(n^2)
+    fn calculate_kendall_tau(&self, x: &[f64], y: &[f64]) -> f64 {
+        let n = x.len();
+        let mut concordant = 0i64;
+        let mut discordant = 0i64;
+        let mut ties_x = 0i64;  // pairs tied in x, including joint ties
+        let mut ties_y = 0i64;  // pairs tied in y, including joint ties
+
+        for i in 0..n {
+            for j in (i + 1)..n {
+                let dx = x[i] - x[j];
+                let dy = y[i] - y[j];
+
+                if dx == 0.0 {
+                    ties_x += 1;
+                }
+                if dy == 0.0 {
+                    ties_y += 1;
+                }
+                if dx == 0.0 || dy == 0.0 {
+                    continue;
+                }
+
+                if (dx > 0.0) == (dy > 0.0) {
+                    concordant += 1;
+                } else {
+                    discordant += 1;
+                }
+            }
+        }
+
+        let total_pairs = (n * n.saturating_sub(1) / 2) as f64;
+        let denominator = ((total_pairs - ties_x as f64) * (total_pairs - ties_y as f64)).sqrt();
+
+        if denominator == 0.0 {
+            0.0
+        } else {
+            (concordant - discordant) as f64 / denominator
+        }
+    }
+
     /// Calculate ranks for Spearman correlation
     fn calculate_ranks(&self, data: &[f64]) -> Vec<f64> {
         let mut indexed: Vec<(usize, f64)> = data.iter()
@@ -323,7 +909,7 @@ impl StatsEngine {
             .map(|(i, &v)| (i, v))
             .collect();
 
-        indexed.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        indexed.sort_by(|a, b| a.1.total_cmp(&b.1));
 
         let mut ranks = vec![0.0; data.len()];
         let mut i = 0;
@@ -355,12 +941,15 @@ impl StatsEngine {
     #[wasm_bindgen(js_name = linearRegression)]
     pub fn linear_regression(&self, x_json: &str, y_json: &str) -> Result<String, JsValue> {
         let x: Vec<f64> = serde_json::from_str(x_json)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse x data: {}", e)))?;
+            .map_err(|e| StatsError::parse(format!("Failed to parse x data: {}", e)).with_field("x_json"))?;
         let y: Vec<f64> = serde_json::from_str(y_json)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse y data: {}", e)))?;
+            .map_err(|e| StatsError::parse(format!("Failed to parse y data: {}", e)).with_field("y_json"))?;
+
+        self.ensure_finite(&x, "x data", "x_json")?;
+        self.ensure_finite(&y, "y data", "y_json")?;
 
         if x.len() != y.len() || x.is_empty() {
-            return Err(JsValue::from_str("Invalid data arrays"));
+            return Err(StatsError::validation("Invalid data arrays").with_field("y_json").into());
         }
 
         let n = x.len() as f64;
@@ -402,19 +991,204 @@ impl StatsEngine {
         };
 
         serde_json::to_string(&result)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
+    /// Fit a least-squares polynomial of the given degree
+    #[wasm_bindgen(js_name = polynomialRegression)]
+    pub fn polynomial_regression(&self, x_json: &str, y_json: &str, degree: usize) -> Result<String, JsValue> {
+        let x: Vec<f64> = serde_json::from_str(x_json)
+            .map_err(|e| StatsError::parse(format!("Failed to parse x data: {}", e)).with_field("x_json"))?;
+        let y: Vec<f64> = serde_json::from_str(y_json)
+            .map_err(|e| StatsError::parse(format!("Failed to parse y data: {}", e)).with_field("y_json"))?;
+
+        if x.len() != y.len() || x.is_empty() {
+            return Err(StatsError::validation("Invalid data arrays").with_field("y_json").into());
+        }
+
+        if degree >= x.len() {
+            return Err(StatsError::validation(format!(
+                "Degree {} needs at least {} points, got {}",
+                degree, degree + 1, x.len()
+            )).with_field("degree").into());
+        }
+
+        // Design rows are [1, x, x^2, ..., x^degree]
+        let design: Vec<Vec<f64>> = x.iter()
+            .map(|xi| (0..=degree).map(|p| xi.powi(p as i32)).collect())
+            .collect();
+
+        let (xtx, xty) = self.normal_equations(&design, &y);
+        let mut coefficients = self.solve_linear_system(xtx, xty)
+            .map_err(|e| StatsError::computation(format!("Polynomial regression failed: {}", e)))?;
+
+        let predictions: Vec<f64> = design.iter()
+            .map(|row| row.iter().zip(&coefficients).map(|(a, c)| a * c).sum())
+            .collect();
+        let (r_squared, _) = self.fit_quality(&y, &predictions);
+
+        coefficients.reverse();
+
+        let result = PolynomialRegressionResult {
+            coefficients,
+            r_squared,
+            predictions,
+        };
+
+        serde_json::to_string(&result)
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
+    /// Fit y against several features by ordinary least squares. Each row
+    /// of the matrix is one observation.
+    #[wasm_bindgen(js_name = multipleRegression)]
+    pub fn multiple_regression(&self, x_matrix_json: &str, y_json: &str) -> Result<String, JsValue> {
+        let rows: Vec<Vec<f64>> = serde_json::from_str(x_matrix_json)
+            .map_err(|e| StatsError::parse(format!("Failed to parse x matrix: {}", e)).with_field("x_matrix_json"))?;
+        let y: Vec<f64> = serde_json::from_str(y_json)
+            .map_err(|e| StatsError::parse(format!("Failed to parse y data: {}", e)).with_field("y_json"))?;
+
+        if rows.len() != y.len() || rows.is_empty() {
+            return Err(StatsError::validation("x matrix and y must have the same, non-zero number of rows").with_field("y_json").into());
+        }
+
+        let features = rows[0].len();
+        if let Some(i) = rows.iter().position(|row| row.len() != features) {
+            return Err(StatsError::validation(format!(
+                "Row {} has {} features, expected {}", i, rows[i].len(), features
+            )).with_field("x_matrix_json").into());
+        }
+
+        let params = features + 1;
+        if rows.len() < params {
+            return Err(StatsError::validation(format!(
+                "{} features need at least {} rows, got {}", features, params, rows.len()
+            )).with_field("x_matrix_json").into());
+        }
+
+        let design: Vec<Vec<f64>> = rows.iter()
+            .map(|row| std::iter::once(1.0).chain(row.iter().copied()).collect())
+            .collect();
+
+        let (xtx, xty) = self.normal_equations(&design, &y);
+        let coefficients = self.solve_linear_system(xtx.clone(), xty)
+            .map_err(|e| StatsError::computation(format!("Multiple regression failed: {}", e)))?;
+
+        let predictions: Vec<f64> = design.iter()
+            .map(|row| row.iter().zip(&coefficients).map(|(a, c)| a * c).sum())
+            .collect();
+        let (r_squared, ss_res) = self.fit_quality(&y, &predictions);
+
+        // With rows == params there are no residual degrees of freedom, so
+        // the error variance and adjusted R-squared are undefined (null)
+        let n = rows.len() as f64;
+        let dof = n - params as f64;
+        let sigma_squared = ss_res / dof;
+        let adjusted_r_squared = 1.0 - (1.0 - r_squared) * (n - 1.0) / dof;
+
+        // Diagonal of (X^T X)^-1, one column of the inverse at a time
+        let mut standard_errors = Vec::with_capacity(params);
+        for j in 0..params {
+            let mut unit = vec![0.0; params];
+            unit[j] = 1.0;
+            let column = self.solve_linear_system(xtx.clone(), unit)
+                .map_err(|e| StatsError::computation(format!("Multiple regression failed: {}", e)))?;
+            standard_errors.push((sigma_squared * column[j]).sqrt());
+        }
+
+        let result = MultipleRegressionResult {
+            coefficients,
+            standard_errors,
+            r_squared,
+            adjusted_r_squared,
+            predictions,
+        };
+
+        serde_json::to_string(&result)
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
+    /// Build X^T X and X^T y for least squares
+    fn normal_equations(&self, design: &[Vec<f64>], y: &[f64]) -> (Vec<Vec<f64>>, Vec<f64>) {
+        let k = design.first().map_or(0, |row| row.len());
+        let mut xtx = vec![vec![0.0; k]; k];
+        let mut xty = vec![0.0; k];
+
+        for (row, yi) in design.iter().zip(y) {
+            for i in 0..k {
+                xty[i] += row[i] * yi;
+                for j in 0..k {
+                    xtx[i][j] += row[i] * row[j];
+                }
+            }
+        }
+
+        (xtx, xty)
+    }
+
+    /// Solve A x = b by Gaussian elimination with partial pivoting
+    fn solve_linear_system(&self, mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Result<Vec<f64>, String> {
+        let n = b.len();
+        let scale = a.iter().flatten().fold(0.0_f64, |m, v| m.max(v.abs()));
+        let tolerance = scale * 1e-12;
+
+        for col in 0..n {
+            let pivot = (col..n)
+                .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+                .unwrap_or(col);
+
+            if a[pivot][col].is_nan() || a[pivot][col].abs() <= tolerance {
+                return Err("system is singular or nearly singular".to_string());
+            }
+
+            a.swap(col, pivot);
+            b.swap(col, pivot);
+
+            let (upper, lower) = a.split_at_mut(col + 1);
+            let pivot_row = &upper[col];
+            for (offset, target) in lower.iter_mut().enumerate() {
+                let factor = target[col] / pivot_row[col];
+                for (value, pivot_value) in target[col..].iter_mut().zip(&pivot_row[col..]) {
+                    *value -= factor * pivot_value;
+                }
+                b[col + 1 + offset] -= factor * b[col];
+            }
+        }
+
+        let mut solution = vec![0.0; n];
+        for row in (0..n).rev() {
+            let tail: f64 = ((row + 1)..n).map(|k| a[row][k] * solution[k]).sum();
+            solution[row] = (b[row] - tail) / a[row][row];
+        }
+
+        Ok(solution)
+    }
+
+    /// R-squared and residual sum of squares for fitted predictions
+    fn fit_quality(&self, y: &[f64], predictions: &[f64]) -> (f64, f64) {
+        let y_mean = y.iter().sum::<f64>() / y.len() as f64;
+        let ss_res: f64 = y.iter()
+            .zip(predictions)
+            .map(|(yi, pred)| (yi - pred).powi(2))
+            .sum();
+        let ss_tot: f64 = y.iter()
+            .map(|yi| (yi - y_mean).powi(2))
+            .sum();
+
+        let r_squared = if ss_tot == 0.0 { 0.0 } else { 1.0 - (ss_res / ss_tot) };
+        (r_squared, ss_res)
     }
 
     /// Aggregate data by groups
     #[wasm_bindgen(js_name = aggregateByGroup)]
     pub fn aggregate_by_group(&self, data_json: &str, groups_json: &str) -> Result<String, JsValue> {
         let data: Vec<f64> = serde_json::from_str(data_json)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse data: {}", e)))?;
+            .map_err(|e| StatsError::parse(format!("Failed to parse data: {}", e)).with_field("data_json"))?;
         let groups: Vec<String> = serde_json::from_str(groups_json)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse groups: {}", e)))?;
+            .map_err(|e| StatsError::parse(format!("Failed to parse groups: {}", e)).with_field("groups_json"))?;
 
         if data.len() != groups.len() {
-            return Err(JsValue::from_str("Data and groups must have the same length"));
+            return Err(StatsError::validation("Data and groups must have the same length").with_field("groups_json").into());
         }
 
         let mut group_data: AHashMap<String, Vec<f64>> = AHashMap::new();
@@ -454,44 +1228,205 @@ impl StatsEngine {
         };
 
         serde_json::to_string(&result)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
     }
 
-    /// Calculate moving average
-    #[wasm_bindgen(js_name = movingAverage)]
-    pub fn moving_average(&self, data_json: &str, window_size: usize) -> Result<String, JsValue> {
-        let data: Vec<f64> = serde_json::from_str(data_json)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse data: {}", e)))?;
+    /// Bin data into `bin_count` equal-width bins spanning min..max
+    #[wasm_bindgen(js_name = histogram)]
+    pub fn histogram(&self, data_json: &str, bin_count: usize, dataset: Option<String>) -> Result<String, JsValue> {
+        let data = self.load_data(data_json, dataset.as_deref())?;
 
-        if window_size == 0 || window_size > data.len() {
-            return Err(JsValue::from_str("Invalid window size"));
+        if bin_count == 0 {
+            return Err(StatsError::validation("Bin count must be at least 1").with_field("bin_count").into());
         }
 
-        let mut moving_averages = Vec::with_capacity(data.len() - window_size + 1);
+        if data.is_empty() {
+            return Err(StatsError::empty_input("Data array is empty").into());
+        }
 
-        for i in 0..=(data.len() - window_size) {
-            let window_sum: f64 = data[i..i + window_size].iter().sum();
-            moving_averages.push(window_sum / window_size as f64);
+        let min = data.iter().fold(f64::INFINITY, |a, &b| a.min(b));
+        let max = data.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
+        let width = (max - min) / bin_count as f64;
+
+        let mut edges: Vec<f64> = (0..bin_count).map(|i| min + width * i as f64).collect();
+        edges.push(max);  // exact, so the maximum always lands in the last bin
+
+        let result = self.bin_values(&data, edges);
+
+        serde_json::to_string(&result)
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
+    /// Bin data using custom, strictly increasing edges. Values outside
+    /// the edges are counted in out_of_range.
+    #[wasm_bindgen(js_name = histogramWithEdges)]
+    pub fn histogram_with_edges(&self, data_json: &str, edges_json: &str, dataset: Option<String>) -> Result<String, JsValue> {
+        let data = self.load_data(data_json, dataset.as_deref())?;
+        let edges: Vec<f64> = serde_json::from_str(edges_json)
+            .map_err(|e| StatsError::parse(format!("Failed to parse edges: {}", e)).with_field("edges_json"))?;
+
+        if edges.len() < 2 {
+            return Err(StatsError::validation("At least two edges are required").with_field("edges_json").into());
+        }
+
+        if edges.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(StatsError::validation("Edges must be strictly increasing").with_field("edges_json").into());
         }
 
+        let result = self.bin_values(&data, edges);
+
+        serde_json::to_string(&result)
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
+    /// Count values into [edge_i, edge_i+1) bins, closing the last bin
+    fn bin_values(&self, data: &[f64], edges: Vec<f64>) -> Histogram {
+        let bins = edges.len() - 1;
+        let mut counts = vec![0; bins];
+        let mut out_of_range = 0;
+        let first = edges[0];
+        let last = edges[bins];
+
+        for &value in data {
+            if value == last {
+                counts[bins - 1] += 1;
+            } else if value >= first && value < last {
+                // Number of edges <= value, minus one, is the bin index
+                let bin = edges.partition_point(|&edge| edge <= value) - 1;
+                counts[bin.min(bins - 1)] += 1;
+            } else {
+                out_of_range += 1;
+            }
+        }
+
+        Histogram { edges, counts, out_of_range }
+    }
+
+    /// Calculate moving average
+    #[wasm_bindgen(js_name = movingAverage)]
+    pub fn moving_average(&self, data_json: &str, window_size: usize, dataset: Option<String>) -> Result<String, JsValue> {
+        let data = self.load_data(data_json, dataset.as_deref())?;
+        self.validate_window(&data, window_size)?;
+
+        let moving_averages = self.moving_average_values(&data, window_size);
+
         serde_json::to_string(&moving_averages)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
+    /// Calculate rolling (population) standard deviation
+    #[wasm_bindgen(js_name = rollingStd)]
+    pub fn rolling_std(&self, data_json: &str, window_size: usize, dataset: Option<String>) -> Result<String, JsValue> {
+        self.rolling(data_json, window_size, dataset.as_deref(), |window| {
+            let n = window.len() as f64;
+            let mean = window.iter().sum::<f64>() / n;
+            (window.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n).sqrt()
+        })
+    }
+
+    /// Calculate rolling minimum
+    #[wasm_bindgen(js_name = rollingMin)]
+    pub fn rolling_min(&self, data_json: &str, window_size: usize, dataset: Option<String>) -> Result<String, JsValue> {
+        self.rolling(data_json, window_size, dataset.as_deref(), |window| {
+            window.iter().fold(f64::INFINITY, |a, &b| a.min(b))
+        })
+    }
+
+    /// Calculate rolling maximum
+    #[wasm_bindgen(js_name = rollingMax)]
+    pub fn rolling_max(&self, data_json: &str, window_size: usize, dataset: Option<String>) -> Result<String, JsValue> {
+        self.rolling(data_json, window_size, dataset.as_deref(), |window| {
+            window.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b))
+        })
+    }
+
+    /// Apply `f` to every full window, giving data.len() - window_size + 1 values
+    fn rolling(&self, data_json: &str, window_size: usize, dataset: Option<&str>, f: impl Fn(&[f64]) -> f64) -> Result<String, JsValue> {
+        let data = self.load_data(data_json, dataset)?;
+        self.validate_window(&data, window_size)?;
+
+        let values: Vec<f64> = data.windows(window_size).map(f).collect();
+
+        serde_json::to_string(&values)
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
+    /// Running total; empty input gives []
+    #[wasm_bindgen(js_name = cumulativeSum)]
+    pub fn cumulative_sum(&self, data_json: &str, dataset: Option<String>) -> Result<String, JsValue> {
+        self.cumulative(data_json, dataset.as_deref(), |acc, x, _| acc + x)
+    }
+
+    /// Running maximum; empty input gives []
+    #[wasm_bindgen(js_name = cumulativeMax)]
+    pub fn cumulative_max(&self, data_json: &str, dataset: Option<String>) -> Result<String, JsValue> {
+        self.cumulative(data_json, dataset.as_deref(), |acc, x, _| acc.max(x))
+    }
+
+    /// Running mean; empty input gives []
+    #[wasm_bindgen(js_name = cumulativeMean)]
+    pub fn cumulative_mean(&self, data_json: &str, dataset: Option<String>) -> Result<String, JsValue> {
+        // Incremental update avoids the running sum growing without bound
+        self.cumulative(data_json, dataset.as_deref(), |acc, x, n| acc + (x - acc) / n as f64)
+    }
+
+    /// Scan the data with `step(previous, value, count)`, seeded by the first value
+    fn cumulative(&self, data_json: &str, dataset: Option<&str>, step: impl Fn(f64, f64, usize) -> f64) -> Result<String, JsValue> {
+        let data = self.load_data(data_json, dataset)?;
+
+        let mut values = Vec::with_capacity(data.len());
+        for (i, &x) in data.iter().enumerate() {
+            let next = match values.last() {
+                Some(&previous) => step(previous, x, i + 1),
+                None => x,
+            };
+            values.push(next);
+        }
+
+        serde_json::to_string(&values)
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
+    fn validate_window(&self, data: &[f64], window_size: usize) -> Result<(), StatsError> {
+        if window_size == 0 || window_size > data.len() {
+            return Err(StatsError::validation("Invalid window size").with_field("window_size"));
+        }
+        Ok(())
     }
 
     /// Calculate exponential smoothing
     #[wasm_bindgen(js_name = exponentialSmoothing)]
-    pub fn exponential_smoothing(&self, data_json: &str, alpha: f64) -> Result<String, JsValue> {
-        let data: Vec<f64> = serde_json::from_str(data_json)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse data: {}", e)))?;
+    pub fn exponential_smoothing(&self, data_json: &str, alpha: f64, dataset: Option<String>) -> Result<String, JsValue> {
+        let data = self.load_data(data_json, dataset.as_deref())?;
 
         if data.is_empty() {
-            return Err(JsValue::from_str("Data array is empty"));
+            return Err(StatsError::empty_input("Data array is empty").into());
         }
 
         if alpha < 0.0 || alpha > 1.0 {
-            return Err(JsValue::from_str("Alpha must be between 0 and 1"));
+            return Err(StatsError::validation("Alpha must be between 0 and 1").with_field("alpha").into());
+        }
+
+        let smoothed = self.exponential_smoothing_values(&data, alpha);
+
+        serde_json::to_string(&smoothed)
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
+    /// Trailing moving average; expects 0 < window_size <= data.len()
+    fn moving_average_values(&self, data: &[f64], window_size: usize) -> Vec<f64> {
+        let mut moving_averages = Vec::with_capacity(data.len() - window_size + 1);
+
+        for i in 0..=(data.len() - window_size) {
+            let window_sum: f64 = data[i..i + window_size].iter().sum();
+            moving_averages.push(window_sum / window_size as f64);
         }
 
+        moving_averages
+    }
+
+    /// Exponential smoothing seeded with the first value; expects non-empty data
+    fn exponential_smoothing_values(&self, data: &[f64], alpha: f64) -> Vec<f64> {
         let mut smoothed = Vec::with_capacity(data.len());
         smoothed.push(data[0]);  // First value is the initial value
 
@@ -500,31 +1435,117 @@ impl StatsEngine {
             smoothed.push(smoothed_value);
         }
 
-        serde_json::to_string(&smoothed)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+        smoothed
     }
 
-    /// Detect anomalies using Z-score method
-    #[wasm_bindgen(js_name = detectAnomalies)]
-    pub fn detect_anomalies(&self, data_json: &str, z_threshold: f64) -> Result<String, JsValue> {
-        let data: Vec<f64> = serde_json::from_str(data_json)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse data: {}", e)))?;
+    /// Additive seasonal decomposition. Trend is a centered moving average
+    /// (2x`period` when `period` is even), so trend and residual are null
+    /// for the first and last period/2 points. Moving average uses a
+    /// trailing window of `period`; exponential smoothing uses
+    /// alpha = 2 / (period + 1).
+    #[wasm_bindgen(js_name = decompose)]
+    pub fn decompose(&self, data_json: &str, period: usize, dataset: Option<String>) -> Result<String, JsValue> {
+        let data = self.load_data(data_json, dataset.as_deref())?;
+
+        if period < 2 {
+            return Err(StatsError::validation("Period must be at least 2").with_field("period").into());
+        }
 
-        if data.is_empty() {
-            return Err(JsValue::from_str("Data array is empty"));
+        if data.len() < 2 * period {
+            return Err(StatsError::validation(format!(
+                "Decomposition needs at least {} points for period {}, got {}",
+                2 * period, period, data.len()
+            )).with_field("period").into());
         }
 
-        let n = data.len() as f64;
-        let mean: f64 = data.iter().sum::<f64>() / n;
-        let variance: f64 = data.iter()
-            .map(|x| (x - mean).powi(2))
-            .sum::<f64>() / n;
-        let std_dev = variance.sqrt();
+        let n = data.len();
+        let half = period / 2;
+
+        // Centered moving average
+        let mut trend = vec![f64::NAN; n];
+        for (i, value) in trend.iter_mut().enumerate().take(n - half).skip(half) {
+            *value = if period.is_multiple_of(2) {
+                // Average of two adjacent period-length windows, which
+                // weights the end points by one half
+                let inner: f64 = data[i - half + 1..i + half].iter().sum();
+                (inner + 0.5 * (data[i - half] + data[i + half])) / period as f64
+            } else {
+                data[i - half..=i + half].iter().sum::<f64>() / period as f64
+            };
+        }
 
-        if std_dev == 0.0 {
-            return Ok("[]".to_string());  // No anomalies if no variation
+        // Average the detrended values at each position in the cycle, then
+        // center so the seasonal component sums to zero over one period
+        let mut season_sums = vec![0.0; period];
+        let mut season_counts = vec![0usize; period];
+        for (i, (value, t)) in data.iter().zip(&trend).enumerate() {
+            if !t.is_nan() {
+                season_sums[i % period] += value - t;
+                season_counts[i % period] += 1;
+            }
+        }
+
+        let season_means: Vec<f64> = season_sums.iter()
+            .zip(&season_counts)
+            .map(|(sum, &count)| sum / count as f64)
+            .collect();
+        let season_offset = season_means.iter().sum::<f64>() / period as f64;
+
+        let seasonal: Vec<f64> = (0..n)
+            .map(|i| season_means[i % period] - season_offset)
+            .collect();
+
+        let residual: Vec<f64> = data.iter()
+            .zip(&trend)
+            .zip(&seasonal)
+            .map(|((value, t), s)| value - t - s)
+            .collect();
+
+        let result = TimeSeriesStats {
+            trend,
+            seasonal,
+            residual,
+            moving_average: self.moving_average_values(&data, period),
+            exponential_smoothing: self.exponential_smoothing_values(&data, 2.0 / (period as f64 + 1.0)),
+        };
+
+        serde_json::to_string(&result)
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
+    /// Detect anomalies by z-score. `method` is "zscore" (default, mean and
+    /// std dev) or "modified_zscore" (median and scaled MAD). With
+    /// `include_inliers` the result is `{anomalies, inliers}` where inliers
+    /// holds the indices of the remaining points; otherwise it is the
+    /// anomaly array alone.
+    #[wasm_bindgen(js_name = detectAnomalies)]
+    pub fn detect_anomalies(
+        &self,
+        data_json: &str,
+        z_threshold: f64,
+        dataset: Option<String>,
+        method: Option<String>,
+        include_inliers: Option<bool>,
+    ) -> Result<String, JsValue> {
+        let data = self.load_data(data_json, dataset.as_deref())?;
+
+        if data.is_empty() {
+            return Err(StatsError::empty_input("Data array is empty").into());
         }
 
+        let (center, spread) = match method.as_deref().unwrap_or("zscore") {
+            "zscore" => {
+                let n = data.len() as f64;
+                let mean: f64 = data.iter().sum::<f64>() / n;
+                let variance: f64 = data.iter()
+                    .map(|x| (x - mean).powi(2))
+                    .sum::<f64>() / n;
+                (mean, variance.sqrt())
+            }
+            "modified_zscore" => self.median_absolute_deviation(&data),
+            other => return Err(StatsError::validation(format!("Unknown anomaly method: {}", other)).with_field("method").into()),
+        };
+
         #[derive(Serialize)]
         struct Anomaly {
             index: usize,
@@ -532,20 +1553,84 @@ impl StatsEngine {
             z_score: f64,
         }
 
-        let anomalies: Vec<Anomaly> = data.iter()
-            .enumerate()
-            .filter_map(|(i, &value)| {
-                let z_score = (value - mean) / std_dev;
-                if z_score.abs() > z_threshold {
-                    Some(Anomaly { index: i, value, z_score })
-                } else {
-                    None
-                }
-            })
-            .collect();
+        let mut anomalies: Vec<Anomaly> = Vec::new();
+        let mut inliers: Vec<usize> = Vec::new();
+
+        for (i, &value) in data.iter().enumerate() {
+            // No anomalies if no variation
+            let z_score = if spread == 0.0 { 0.0 } else { (value - center) / spread };
+            if z_score.abs() > z_threshold {
+                anomalies.push(Anomaly { index: i, value, z_score });
+            } else {
+                inliers.push(i);
+            }
+        }
+
+        if include_inliers.unwrap_or(false) {
+            #[derive(Serialize)]
+            struct AnomalyReport {
+                anomalies: Vec<Anomaly>,
+                inliers: Vec<usize>,
+            }
+
+            serde_json::to_string(&AnomalyReport { anomalies, inliers })
+                .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+        } else {
+            serde_json::to_string(&anomalies)
+                .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+        }
+    }
+
+    /// Store a parsed dataset under `name` so later calls can pass the name
+    /// instead of re-sending the array. Replaces any dataset with that name.
+    #[wasm_bindgen(js_name = storeDataset)]
+    pub fn store_dataset(&mut self, name: &str, data_json: &str) -> Result<usize, JsValue> {
+        let data: Vec<f64> = serde_json::from_str(data_json)
+            .map_err(|e| StatsError::parse(format!("Failed to parse data: {}", e)).with_field("data_json"))?;
+
+        let len = data.len();
+        self.data_cache.insert(name.to_string(), data);
+        Ok(len)
+    }
+
+    /// List stored dataset names, sorted
+    #[wasm_bindgen(js_name = listDatasets)]
+    pub fn list_datasets(&self) -> Result<String, JsValue> {
+        let mut names: Vec<&String> = self.data_cache.keys().collect();
+        names.sort();
+
+        serde_json::to_string(&names)
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+    }
+
+    /// Remove a stored dataset, returning whether it existed
+    #[wasm_bindgen(js_name = dropDataset)]
+    pub fn drop_dataset(&mut self, name: &str) -> bool {
+        self.data_cache.remove(name).is_some()
+    }
+
+    /// Reject NaN and infinities, which would poison sorting and sums.
+    /// JSON has no literal for them, so serde_json already refuses them in
+    /// `data_json` with a parse_error; this guards slices from other sources
+    fn ensure_finite(&self, data: &[f64], label: &str, field: &str) -> Result<(), StatsError> {
+        match data.iter().position(|v| !v.is_finite()) {
+            Some(i) => Err(StatsError::validation(format!(
+                "{} contains non-finite value {} at index {}", label, data[i], i
+            )).with_field(field)),
+            None => Ok(()),
+        }
+    }
 
-        serde_json::to_string(&anomalies)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    /// Borrow the named stored dataset when given, otherwise parse `data_json`
+    fn load_data(&self, data_json: &str, dataset: Option<&str>) -> Result<Cow<'_, [f64]>, StatsError> {
+        match dataset {
+            Some(name) => self.data_cache.get(name)
+                .map(|data| Cow::Borrowed(data.as_slice()))
+                .ok_or_else(|| StatsError::not_found(format!("Unknown dataset: {}", name)).with_field("dataset")),
+            None => serde_json::from_str(data_json)
+                .map(Cow::Owned)
+                .map_err(|e| StatsError::parse(format!("Failed to parse data: {}", e)).with_field("data_json")),
+        }
     }
 
     /// Clear all caches
@@ -554,4 +1639,202 @@ impl StatsEngine {
         self.data_cache.clear();
         self.results_cache.clear();
     }
-}
\ No newline at end of file
+}
+
+/// Incremental statistics using Welford's algorithm, so values can be fed
+/// in without storing them
+#[wasm_bindgen]
+pub struct OnlineStats {
+    count: usize,
+    mean: f64,
+    m2: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Default for OnlineStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl OnlineStats {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        OnlineStats {
+            count: 0,
+            mean: 0.0,
+            m2: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    /// Add a single value
+    #[wasm_bindgen(js_name = push)]
+    pub fn push(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Add every value in a JSON array
+    #[wasm_bindgen(js_name = pushBatch)]
+    pub fn push_batch(&mut self, data_json: &str) -> Result<(), JsValue> {
+        let data: Vec<f64> = serde_json::from_str(data_json)
+            .map_err(|e| StatsError::parse(format!("Failed to parse data: {}", e)).with_field("data_json"))?;
+
+        for value in data {
+            self.push(value);
+        }
+        Ok(())
+    }
+
+    /// Fold another accumulator into this one (Chan et al. parallel update)
+    #[wasm_bindgen(js_name = merge)]
+    pub fn merge(&mut self, other: &OnlineStats) {
+        if other.count == 0 {
+            return;
+        }
+
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        self.mean += delta * other.count as f64 / count as f64;
+        self.m2 += other.m2 + delta * delta * (self.count as f64 * other.count as f64) / count as f64;
+        self.count = count;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    /// Current count, mean, variance, std dev, min and max
+    #[wasm_bindgen(js_name = snapshot)]
+    pub fn snapshot(&self) -> Result<String, JsValue> {
+        let result = if self.count == 0 {
+            OnlineSnapshot {
+                count: 0,
+                mean: f64::NAN,
+                variance: f64::NAN,
+                std_dev: f64::NAN,
+                min: f64::NAN,
+                max: f64::NAN,
+            }
+        } else {
+            let variance = self.m2 / self.count as f64;
+            OnlineSnapshot {
+                count: self.count,
+                mean: self.mean,
+                variance,
+                std_dev: variance.sqrt(),
+                min: self.min,
+                max: self.max,
+            }
+        };
+
+        serde_json::to_string(&result)
+            .map_err(|e| StatsError::serialization(format!("Serialization error: {}", e)).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stored_datasets_match_inline_data() {
+        let mut engine = StatsEngine::new();
+        let data = "[3, 1, 4, 1, 5, 9, 2, 6]";
+        engine.store_dataset("prices", data).unwrap();
+        let stored = || Some("prices".to_string());
+
+        // Sorting for stats must work on a copy, leaving the stored order intact
+        assert_eq!(engine.calculate_stats("", stored(), None).unwrap(), engine.calculate_stats(data, None, None).unwrap());
+        assert_eq!(engine.rolling_max("", 2, stored()).unwrap(), "[3.0,4.0,4.0,5.0,9.0,9.0,6.0]");
+
+        assert_eq!(engine.percentiles("", "[25, 50]", None, stored()).unwrap(), engine.percentiles(data, "[25, 50]", None, None).unwrap());
+        assert_eq!(engine.robust_stats("", stored()).unwrap(), engine.robust_stats(data, None).unwrap());
+        assert_eq!(engine.trim("", 10.0, 10.0, stored()).unwrap(), engine.trim(data, 10.0, 10.0, None).unwrap());
+        assert_eq!(engine.rolling_std("", 3, stored()).unwrap(), engine.rolling_std(data, 3, None).unwrap());
+        assert_eq!(engine.cumulative_mean("", stored()).unwrap(), engine.cumulative_mean(data, None).unwrap());
+        assert_eq!(
+            engine.bootstrap_mean_ci("", 200, 0.9, 7, stored()).unwrap(),
+            engine.bootstrap_mean_ci(data, 200, 0.9, 7, None).unwrap()
+        );
+        assert_eq!(
+            engine.weighted_stats("", "[1, 1, 1, 1, 2, 2, 2, 2]", stored()).unwrap(),
+            engine.weighted_stats(data, "[1, 1, 1, 1, 2, 2, 2, 2]", None).unwrap()
+        );
+    }
+
+    #[test]
+    fn rolling_std_matches_brute_force() {
+        let data = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0, 1.5];
+        let json = serde_json::to_string(&data).unwrap();
+        let rolling: Vec<f64> = serde_json::from_str(&StatsEngine::new().rolling_std(&json, 3, None).unwrap()).unwrap();
+
+        // Population variance as the mean squared pairwise difference over 2
+        let expected: Vec<f64> = (0..=data.len() - 3)
+            .map(|start| {
+                let window = &data[start..start + 3];
+                let mut sum = 0.0_f64;
+                for a in window {
+                    for b in window {
+                        sum += (a - b) * (a - b);
+                    }
+                }
+                (sum / (2.0 * 9.0)).sqrt()
+            })
+            .collect();
+
+        assert_eq!(rolling.len(), data.len() - 2);
+        for (got, want) in rolling.iter().zip(&expected) {
+            assert!((got - want).abs() < 1e-12, "{} vs {}", got, want);
+        }
+    }
+
+    #[test]
+    fn non_finite_values_are_structured_errors() {
+        let engine = StatsEngine::new();
+
+        // JSON input never reaches ensure_finite: parsing rejects NaN first
+        let error = engine.load_data("[1.0, NaN, 3.0]", None).unwrap_err();
+        assert_eq!(error.code, ErrorCode::ParseError);
+        assert_eq!(error.field.as_deref(), Some("data_json"));
+
+        let error = engine.ensure_finite(&[1.0, f64::NAN, 3.0], "Data", "data_json").unwrap_err();
+        assert_eq!(error.code, ErrorCode::ValidationError);
+        assert_eq!(error.field.as_deref(), Some("data_json"));
+        assert_eq!(error.message, "Data contains non-finite value NaN at index 1");
+    }
+
+    #[test]
+    fn kendall_tau_b_corrects_for_ties() {
+        // Example from the SciPy kendalltau docs: x has two tied pairs, y one,
+        // leaving 2 concordant and 6 discordant pairs, so
+        // tau-b = (2 - 6) / sqrt((10 - 2) * (10 - 1)) = -sqrt(2) / 3
+        let x = [12.0, 2.0, 1.0, 12.0, 2.0];
+        let y = [1.0, 4.0, 7.0, 1.0, 0.0];
+
+        let tau = StatsEngine::new().calculate_kendall_tau(&x, &y);
+        assert!((tau - -0.47140452079103173).abs() < 1e-12, "{}", tau);
+    }
+
+    #[test]
+    fn mode_buckets_stay_distinct_at_high_precision() {
+        let mut engine = StatsEngine::new();
+        let mode = |engine: &mut StatsEngine, data: &str, decimals: Option<u32>| -> serde_json::Value {
+            let stats: serde_json::Value = serde_json::from_str(&engine.calculate_stats(data, None, decimals).unwrap()).unwrap();
+            stats["mode"].clone()
+        };
+
+        // At 15 decimals these overflow an i64 of value * 10^15
+        let prices = "[9300.5, 9300.5, 9400.25, 9500.75]";
+        assert_eq!(mode(&mut engine, prices, Some(15)), serde_json::json!([9300.5]));
+
+        assert_eq!(mode(&mut engine, "[1.001, 1.004, 2]", None), serde_json::json!([1.0]));
+        assert_eq!(mode(&mut engine, "[1.001, 1.004, 2]", Some(3)), serde_json::json!([]));
+    }
+}